@@ -0,0 +1,266 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    Fixed,
+    Exponential { multiplier: f64, max_interval: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Jitter {
+    None,
+    Full,
+    Equal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    pub strategy: Strategy,
+    pub jitter: Jitter,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            strategy: Strategy::Fixed,
+            jitter: Jitter::None,
+        }
+    }
+}
+
+impl fmt::Display for Jitter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Jitter::None => write!(f, "none"),
+            Jitter::Full => write!(f, "full"),
+            Jitter::Equal => write!(f, "equal"),
+        }
+    }
+}
+
+impl fmt::Display for Backoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.strategy {
+            Strategy::Fixed => write!(f, "fixed")?,
+            Strategy::Exponential {
+                multiplier,
+                max_interval,
+            } => write!(
+                f,
+                "exponential, multiplier {}, max interval {} seconds",
+                multiplier, max_interval
+            )?,
+        }
+        write!(f, ", {} jitter", self.jitter)
+    }
+}
+
+impl Backoff {
+    // The interval never shrinks: a starting interval above the max interval is kept as the max
+    pub fn schedule(&self, initial_interval: u64) -> Schedule {
+        let mut backoff = *self;
+        if let Strategy::Exponential { max_interval, .. } = &mut backoff.strategy {
+            *max_interval = (*max_interval).max(initial_interval);
+        }
+        Schedule {
+            backoff,
+            interval: initial_interval as f64,
+            random: Random::new(),
+        }
+    }
+}
+
+pub struct Schedule {
+    backoff: Backoff,
+    interval: f64,
+    random: Random,
+}
+
+impl Schedule {
+    pub fn next_interval(&mut self) -> u64 {
+        let interval = self.interval.round() as u64;
+        if let Strategy::Exponential {
+            multiplier,
+            max_interval,
+        } = self.backoff.strategy
+        {
+            self.interval = (self.interval * multiplier).min(max_interval as f64);
+        }
+        let jittered = match self.backoff.jitter {
+            Jitter::None => interval,
+            Jitter::Full => self.random.up_to(interval),
+            Jitter::Equal => interval / 2 + self.random.up_to(interval - interval / 2),
+        };
+        // A jittered retry never happens immediately, unless no sleeping time is configured at all
+        jittered.max(interval.min(1))
+    }
+}
+
+pub fn strategy_from_str(strategy: &str, multiplier: f64, max_interval: u64) -> Strategy {
+    match strategy.trim().to_lowercase().as_str() {
+        "exponential" => Strategy::Exponential {
+            multiplier,
+            max_interval,
+        },
+        _ => Strategy::Fixed,
+    }
+}
+
+pub fn jitter_from_str(jitter: &str) -> Jitter {
+    match jitter.trim().to_lowercase().as_str() {
+        "full" => Jitter::Full,
+        "equal" => Jitter::Equal,
+        _ => Jitter::None,
+    }
+}
+
+// A xorshift generator is more than enough to spread retries
+// and avoids pulling a dependency into the executable.
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        Random {
+            state: (nanos ^ (u64::from(std::process::id()) << 32)) | 1,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn up_to(&mut self, max: u64) -> u64 {
+        match max.checked_add(1) {
+            Some(bound) => self.next() % bound,
+            None => self.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn fixed_schedule_should_always_return_the_initial_interval() {
+        let mut schedule = Backoff::default().schedule(3);
+        for _ in 0..10 {
+            assert_eq!(3, schedule.next_interval());
+        }
+    }
+
+    #[test]
+    fn exponential_schedule_should_grow_up_to_the_max_interval() {
+        let backoff = Backoff {
+            strategy: Strategy::Exponential {
+                multiplier: 2.0,
+                max_interval: 20,
+            },
+            jitter: Jitter::None,
+        };
+        let mut schedule = backoff.schedule(1);
+        let intervals: Vec<u64> = (0..8).map(|_| schedule.next_interval()).collect();
+        assert_eq!(vec![1, 2, 4, 8, 16, 20, 20, 20], intervals);
+    }
+
+    #[test]
+    fn exponential_schedule_should_never_shrink_below_the_initial_interval() {
+        let backoff = Backoff {
+            strategy: Strategy::Exponential {
+                multiplier: 2.0,
+                max_interval: 30,
+            },
+            jitter: Jitter::None,
+        };
+        let mut schedule = backoff.schedule(60);
+        let intervals: Vec<u64> = (0..4).map(|_| schedule.next_interval()).collect();
+        assert_eq!(vec![60, 60, 60, 60], intervals);
+    }
+
+    #[test]
+    fn full_jitter_should_stay_between_one_and_the_interval() {
+        let backoff = Backoff {
+            strategy: Strategy::Fixed,
+            jitter: Jitter::Full,
+        };
+        let mut schedule = backoff.schedule(10);
+        let intervals: Vec<u64> = (0..100).map(|_| schedule.next_interval()).collect();
+        assert!(intervals.iter().all(|interval| (1..=10).contains(interval)));
+        assert!(intervals.iter().any(|interval| *interval != intervals[0]));
+    }
+
+    #[test]
+    fn full_jitter_should_never_sleep_zero_on_an_interval_of_one() {
+        let backoff = Backoff {
+            strategy: Strategy::Fixed,
+            jitter: Jitter::Full,
+        };
+        let mut schedule = backoff.schedule(1);
+        for _ in 0..100 {
+            assert_eq!(1, schedule.next_interval());
+        }
+        let mut schedule = backoff.schedule(0);
+        assert_eq!(0, schedule.next_interval());
+    }
+
+    #[test]
+    fn equal_jitter_should_stay_between_half_and_the_interval() {
+        let backoff = Backoff {
+            strategy: Strategy::Fixed,
+            jitter: Jitter::Equal,
+        };
+        let mut schedule = backoff.schedule(11);
+        for _ in 0..100 {
+            let interval = schedule.next_interval();
+            assert!((5..=11).contains(&interval));
+        }
+    }
+
+    #[test]
+    fn should_describe_the_backoff() {
+        assert_eq!("fixed, none jitter", Backoff::default().to_string());
+        let backoff = Backoff {
+            strategy: Strategy::Exponential {
+                multiplier: 1.5,
+                max_interval: 20,
+            },
+            jitter: Jitter::Full,
+        };
+        assert_eq!(
+            "exponential, multiplier 1.5, max interval 20 seconds, full jitter",
+            backoff.to_string()
+        );
+    }
+
+    #[test]
+    fn should_parse_the_strategy() {
+        assert_eq!(Strategy::Fixed, strategy_from_str("", 2.0, 10));
+        assert_eq!(Strategy::Fixed, strategy_from_str("fixed", 2.0, 10));
+        assert_eq!(
+            Strategy::Exponential {
+                multiplier: 1.5,
+                max_interval: 10
+            },
+            strategy_from_str(" Exponential ", 1.5, 10)
+        );
+    }
+
+    #[test]
+    fn should_parse_the_jitter() {
+        assert_eq!(Jitter::None, jitter_from_str(""));
+        assert_eq!(Jitter::None, jitter_from_str("abc"));
+        assert_eq!(Jitter::Full, jitter_from_str("FULL"));
+        assert_eq!(Jitter::Equal, jitter_from_str("equal"));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::status::Status;
+use log::*;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// A bare port listens on every interface, e.g. `7000` is equivalent to `0.0.0.0:7000`
+pub fn to_addr(addr: &str) -> String {
+    let addr = addr.trim();
+    match addr.parse::<u16>() {
+        Ok(port) => format!("0.0.0.0:{}", port),
+        Err(_) => addr.to_string(),
+    }
+}
+
+// Accepts and immediately closes the connections in a background thread, so that other
+// containers can wait for this one as a host. The port is closed while the status
+// is not ready, e.g. when a watched target is no longer available, and opened again later.
+// Returns the bound address.
+pub fn open(addr: &str, status: Status) -> std::io::Result<SocketAddr> {
+    let listener = bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    thread::spawn(move || {
+        let mut listener = Some(listener);
+        loop {
+            match (status.is_ready(), &listener) {
+                (true, Some(listener)) => accept(listener),
+                (true, None) => match bind(&local_addr.to_string()) {
+                    Ok(reopened) => {
+                        info!("Barrier [{}] open again", local_addr);
+                        listener = Some(reopened);
+                    }
+                    Err(e) => warn!("Failed to reopen barrier [{}]: {}", local_addr, e),
+                },
+                (false, Some(_)) => {
+                    info!(
+                        "Barrier [{}] closed until the targets are available",
+                        local_addr
+                    );
+                    listener = None;
+                }
+                (false, None) => {}
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+    Ok(local_addr)
+}
+
+fn bind(addr: &str) -> std::io::Result<TcpListener> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+// The connection is the whole message, the stream is closed once dropped
+fn accept(listener: &TcpListener) {
+    loop {
+        match listener.accept() {
+            Ok((_, peer)) => trace!("Barrier connection from [{}]", peer),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+            Err(e) => {
+                debug!("Failed to accept a barrier connection: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::status::State;
+    use crate::{Config, target};
+    use std::net::TcpStream;
+    use std::time::Instant;
+
+    fn wait_for(expected: bool, addr: SocketAddr) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(2) {
+            if TcpStream::connect(addr).is_ok() == expected {
+                return true;
+            }
+            thread::sleep(POLL_INTERVAL / 2);
+        }
+        false
+    }
+
+    #[test]
+    fn should_parse_the_address() {
+        assert_eq!("0.0.0.0:7000", to_addr(" 7000 "));
+        assert_eq!("127.0.0.1:7000", to_addr("127.0.0.1:7000"));
+        assert_eq!("[::]:7000", to_addr("[::]:7000"));
+    }
+
+    #[test]
+    fn should_close_the_barrier_while_not_ready() {
+        let config = Config {
+            hosts: "db:5432".to_string(),
+            ..Default::default()
+        };
+        let status = Status::default();
+        status.add(&target::from_config(&config).unwrap(), State::Waiting);
+        status.checked(0, &Ok(()), true);
+        status.set_completed();
+
+        let addr = open("127.0.0.1:0", status.clone()).unwrap();
+        assert!(wait_for(true, addr));
+
+        status.checked(0, &Err("connection refused".to_string()), false);
+        assert!(wait_for(false, addr));
+
+        status.checked(0, &Ok(()), true);
+        assert!(wait_for(true, addr));
+    }
+}
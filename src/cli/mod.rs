@@ -0,0 +1,154 @@
+const OPTION_PREFIX: &str = "--";
+const ENV_PREFIX: &str = "WAIT_";
+// Options whose name differs from the one of the environment variable
+const ALIASES: &[(&str, &str)] = &[
+    ("from-compose", "compose-file"),
+    ("service", "compose-service"),
+    ("explain", "dry-run"),
+];
+// Options whose value can be omitted, e.g. `--explain` is equivalent to `--explain=true`
+const FLAGS: &[&str] = &["explain", "dry-run"];
+
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    Wait,
+    Check,
+    Serve,
+}
+
+// The subcommand, if any, is the first argument
+pub fn parse_subcommand<I: IntoIterator<Item = String>>(args: I) -> (Subcommand, Vec<String>) {
+    let mut args: Vec<String> = args.into_iter().collect();
+    let subcommand = match args.first().map(|arg| arg.as_str()) {
+        Some("check") => Subcommand::Check,
+        Some("serve") => Subcommand::Serve,
+        _ => return (Subcommand::Wait, args),
+    };
+    args.remove(0);
+    (subcommand, args)
+}
+
+// Every command line option maps to the environment variable with the same name,
+// e.g. `--backoff-jitter full` is equivalent to `WAIT_BACKOFF_JITTER=full`.
+pub fn parse_args<I: IntoIterator<Item = String>>(
+    args: I,
+) -> Result<Vec<(String, String)>, String> {
+    let mut options = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let option = match arg.strip_prefix(OPTION_PREFIX) {
+            Some(option) if !option.is_empty() => option,
+            _ => return Err(format!("Unexpected argument [{}]", arg)),
+        };
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None if FLAGS.contains(&option) => (option.to_string(), "true".to_string()),
+            None => match args.next() {
+                Some(value) => (option.to_string(), value),
+                None => return Err(format!("Missing value for option [{}]", arg)),
+            },
+        };
+        options.push((to_env_var_name(&name), value));
+    }
+    Ok(options)
+}
+
+fn to_env_var_name(option: &str) -> String {
+    let option = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == option)
+        .map_or(option, |(_, name)| name);
+    format!("{}{}", ENV_PREFIX, option.replace('-', "_").to_uppercase())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn should_return_no_options_without_args() {
+        assert!(parse_args(args(&[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_map_options_to_env_vars() {
+        let options = parse_args(args(&[
+            "--backoff",
+            "exponential",
+            "--backoff-jitter=full",
+            "--hosts=db:5432,cache:6379",
+        ]))
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("WAIT_BACKOFF".to_string(), "exponential".to_string()),
+                ("WAIT_BACKOFF_JITTER".to_string(), "full".to_string()),
+                ("WAIT_HOSTS".to_string(), "db:5432,cache:6379".to_string()),
+            ],
+            options
+        );
+    }
+
+    #[test]
+    fn should_map_aliases_to_env_vars() {
+        let options = parse_args(args(&[
+            "--from-compose",
+            "docker-compose.yml",
+            "--service=app",
+        ]))
+        .unwrap();
+        assert_eq!(
+            vec![
+                (
+                    "WAIT_COMPOSE_FILE".to_string(),
+                    "docker-compose.yml".to_string()
+                ),
+                ("WAIT_COMPOSE_SERVICE".to_string(), "app".to_string()),
+            ],
+            options
+        );
+    }
+
+    #[test]
+    fn should_map_flags_to_env_vars() {
+        let options =
+            parse_args(args(&["--explain", "--hosts", "db:5432", "--dry-run=json"])).unwrap();
+        assert_eq!(
+            vec![
+                ("WAIT_DRY_RUN".to_string(), "true".to_string()),
+                ("WAIT_HOSTS".to_string(), "db:5432".to_string()),
+                ("WAIT_DRY_RUN".to_string(), "json".to_string()),
+            ],
+            options
+        );
+    }
+
+    #[test]
+    fn should_parse_the_subcommand() {
+        assert_eq!((Subcommand::Wait, vec![]), parse_subcommand(args(&[])));
+        assert_eq!(
+            (Subcommand::Wait, args(&["--hosts", "check"])),
+            parse_subcommand(args(&["--hosts", "check"]))
+        );
+        assert_eq!(
+            (Subcommand::Check, args(&["--hosts", "db:5432"])),
+            parse_subcommand(args(&["check", "--hosts", "db:5432"]))
+        );
+        assert_eq!(
+            (Subcommand::Serve, args(&["--barrier-addr", "7000"])),
+            parse_subcommand(args(&["serve", "--barrier-addr", "7000"]))
+        );
+    }
+
+    #[test]
+    fn should_fail_on_unexpected_args() {
+        assert!(parse_args(args(&["backoff"])).is_err());
+        assert!(parse_args(args(&["--"])).is_err());
+        assert!(parse_args(args(&["--backoff"])).is_err());
+    }
+}
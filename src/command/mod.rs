@@ -0,0 +1,142 @@
+use crate::Command;
+use std::io::Read;
+use std::process::{Child, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const OUTPUT_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+pub struct Outcome {
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub output: String,
+}
+
+pub fn run_with_timeout(command: &Command, timeout: Duration) -> std::io::Result<Outcome> {
+    run_with_env(command, &[], timeout)
+}
+
+// Runs the command with additional environment variables
+pub fn run_with_env(
+    command: &Command,
+    env: &[(&str, String)],
+    timeout: Duration,
+) -> std::io::Result<Outcome> {
+    let mut child = std::process::Command::new(&command.program)
+        .args(&command.argv)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (sender, receiver) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        read_async(stdout, sender.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        read_async(stderr, sender);
+    }
+
+    let started_at = Instant::now();
+    let (status, timed_out) = loop {
+        if let Some(status) = child.try_wait()? {
+            break (Some(status), false);
+        }
+        if started_at.elapsed() >= timeout {
+            kill(&mut child);
+            break (None, true);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    // Processes spawned by the command could keep the pipes open,
+    // so the output is collected without waiting indefinitely for it.
+    let mut output = String::new();
+    while let Ok(chunk) = receiver.recv_timeout(OUTPUT_GRACE_PERIOD) {
+        output.push_str(&chunk);
+    }
+
+    Ok(Outcome {
+        exit_code: status.and_then(|status| status.code()),
+        timed_out,
+        output: output.trim().to_string(),
+    })
+}
+
+fn read_async<R: Read + Send + 'static>(mut reader: R, sender: mpsc::Sender<String>) {
+    thread::spawn(move || {
+        let mut buffer = vec![];
+        let _ = reader.read_to_end(&mut buffer);
+        let _ = sender.send(String::from_utf8_lossy(&buffer).to_string());
+    });
+}
+
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::parse_command;
+
+    fn command(raw_cmd: &str) -> Command {
+        parse_command(raw_cmd).unwrap().unwrap().0
+    }
+
+    #[test]
+    fn should_return_the_exit_code() {
+        let outcome = run_with_timeout(&command("true"), Duration::from_secs(5)).unwrap();
+        assert_eq!(Some(0), outcome.exit_code);
+        assert!(!outcome.timed_out);
+
+        let outcome = run_with_timeout(&command("sh -c 'exit 3'"), Duration::from_secs(5)).unwrap();
+        assert_eq!(Some(3), outcome.exit_code);
+        assert!(!outcome.timed_out);
+    }
+
+    #[test]
+    fn should_capture_the_output() {
+        let outcome = run_with_timeout(
+            &command("sh -c 'echo hello; echo world >&2; exit 1'"),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(Some(1), outcome.exit_code);
+        assert!(outcome.output.contains("hello"));
+        assert!(outcome.output.contains("world"));
+    }
+
+    #[test]
+    fn should_pass_the_environment_variables() {
+        let outcome = run_with_env(
+            &command("sh -c 'echo $WAIT_TEST_A-$WAIT_TEST_B'"),
+            &[
+                ("WAIT_TEST_A", "a".to_string()),
+                ("WAIT_TEST_B", "b".to_string()),
+            ],
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!("a-b", outcome.output);
+    }
+
+    #[test]
+    fn should_kill_a_hung_command() {
+        let start = Instant::now();
+        let outcome = run_with_timeout(&command("sleep 10"), Duration::from_millis(200)).unwrap();
+        assert!(outcome.timed_out);
+        assert_eq!(None, outcome.exit_code);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn should_fail_if_the_command_does_not_exist() {
+        assert!(run_with_timeout(&command("fsdfsdfsdfwerwer"), Duration::from_secs(1)).is_err());
+    }
+}
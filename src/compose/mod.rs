@@ -0,0 +1,254 @@
+use log::*;
+use serde_json::Value;
+use std::fs;
+
+const SERVICES_KEY: &str = "services";
+const DEPENDS_ON_KEY: &str = "depends_on";
+const CONDITION_KEY: &str = "condition";
+const COMPLETED_CONDITION: &str = "service_completed_successfully";
+const EXPOSE_KEY: &str = "expose";
+const PORTS_KEY: &str = "ports";
+const HEALTHCHECK_KEY: &str = "healthcheck";
+const LOCAL_HOSTS: &[&str] = &["localhost:", "127.0.0.1:", "0.0.0.0:"];
+const UDP_PROTOCOL: &str = "udp";
+
+// Derives the host:port targets of a service from the `depends_on` entries of a docker-compose file.
+// Every dependency is reachable through its service name on the ports of its `expose`, `ports`
+// and `healthcheck` definitions.
+pub fn targets(path: &str, service: &str) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read compose file [{}]: {}", path, e))?;
+    let compose: Value = serde_yaml_ng::from_str(&content)
+        .map_err(|e| format!("cannot parse compose file [{}]: {}", path, e))?;
+    let services = compose
+        .get(SERVICES_KEY)
+        .and_then(Value::as_object)
+        .ok_or_else(|| format!("no services found in compose file [{}]", path))?;
+    let definition = services
+        .get(service)
+        .ok_or_else(|| format!("service [{}] not found in compose file [{}]", service, path))?;
+
+    let mut targets = vec![];
+    for dependency in dependencies(definition) {
+        let ports = match services.get(&dependency) {
+            Some(definition) => ports(definition),
+            None => {
+                return Err(format!(
+                    "dependency [{}] of service [{}] not found in compose file [{}]",
+                    dependency, service, path
+                ));
+            }
+        };
+        if ports.is_empty() {
+            warn!(
+                "No port found for the dependency [{}] of service [{}], it will not be waited for",
+                dependency, service
+            );
+        }
+        targets.extend(ports.iter().map(|port| format!("{}:{}", dependency, port)));
+    }
+    Ok(targets)
+}
+
+// `depends_on` is either a list of services or a map of services with their conditions.
+// Services expected to complete are one-shot jobs without ports, so they are skipped.
+fn dependencies(definition: &Value) -> Vec<String> {
+    match definition.get(DEPENDS_ON_KEY) {
+        Some(Value::Array(services)) => services
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::Object(services)) => services
+            .iter()
+            .filter(|(name, options)| {
+                let completed =
+                    options.get(CONDITION_KEY).and_then(Value::as_str) == Some(COMPLETED_CONDITION);
+                if completed {
+                    info!("Skipping dependency [{}] expected to complete", name);
+                }
+                !completed
+            })
+            .map(|(name, _)| name.clone())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn ports(definition: &Value) -> Vec<u16> {
+    let mut ports: Vec<u16> = entries(definition, EXPOSE_KEY)
+        .iter()
+        .chain(entries(definition, PORTS_KEY).iter())
+        .filter_map(container_port)
+        .chain(healthcheck_ports(definition))
+        .collect();
+    ports.sort();
+    ports.dedup();
+    ports
+}
+
+fn entries(definition: &Value, key: &str) -> Vec<Value> {
+    match definition.get(key) {
+        Some(Value::Array(entries)) => entries.clone(),
+        _ => vec![],
+    }
+}
+
+// Supports the short syntax, e.g. `5432`, `"8080:80"`, `"127.0.0.1:8080:80/tcp"` or `"8000-8010"`,
+// where only the first port of a range is used, and the long syntax, e.g. `{ target: 80, published: 8080 }`
+fn container_port(entry: &Value) -> Option<u16> {
+    match entry {
+        Value::Number(port) => port.as_u64().and_then(|port| u16::try_from(port).ok()),
+        Value::String(entry) => {
+            let (port, protocol) = entry.split_once('/').unwrap_or((entry, ""));
+            if protocol.eq_ignore_ascii_case(UDP_PROTOCOL) {
+                return None;
+            }
+            let port = port.rsplit(':').next().unwrap_or_default();
+            let port = port.split('-').next().unwrap_or_default();
+            port.trim().parse().ok()
+        }
+        Value::Object(options) => {
+            let udp = options.get("protocol").and_then(Value::as_str) == Some(UDP_PROTOCOL);
+            match options.get("target") {
+                Some(target) if !udp => container_port(target),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// The ports probed by the healthcheck on the local host, e.g. `curl -f http://localhost:8080/health`
+fn healthcheck_ports(definition: &Value) -> Vec<u16> {
+    let test = match definition
+        .get(HEALTHCHECK_KEY)
+        .and_then(|check| check.get("test"))
+    {
+        Some(Value::String(test)) => test.clone(),
+        Some(Value::Array(args)) => args
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<&str>>()
+            .join(" "),
+        _ => return vec![],
+    };
+    LOCAL_HOSTS
+        .iter()
+        .flat_map(|host| {
+            test.match_indices(host)
+                .map(|(index, _)| &test[index + host.len()..])
+        })
+        .filter_map(|rest| {
+            let port: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            port.parse().ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn write_file(content: &str) -> String {
+        let path = format!("./target/{}.yml", rand::random::<u64>());
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    const COMPOSE: &str = r#"
+services:
+  app:
+    image: app
+    depends_on:
+      postgres:
+        condition: service_healthy
+      redis:
+        condition: service_started
+      migrations:
+        condition: service_completed_successfully
+      api:
+        condition: service_started
+  worker:
+    image: worker
+    depends_on: [redis, dns, worker-cache]
+  postgres:
+    image: postgres
+    expose: ["5432"]
+  redis:
+    image: redis
+    ports:
+      - "127.0.0.1:16379:6379/tcp"
+      - 6380
+      - target: 6381
+        published: 16381
+      - "5353:53/udp"
+  api:
+    image: api
+    healthcheck:
+      test: ["CMD", "curl", "-f", "http://localhost:8080/health"]
+  migrations:
+    image: migrations
+  dns:
+    image: dns
+    ports: ["53:53/udp"]
+"#;
+
+    #[test]
+    fn should_derive_the_targets_from_the_dependencies() {
+        let path = write_file(COMPOSE);
+        assert_eq!(
+            vec![
+                "postgres:5432",
+                "redis:6379",
+                "redis:6380",
+                "redis:6381",
+                "api:8080"
+            ],
+            targets(&path, "app").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_skip_dependencies_without_ports() {
+        let path = write_file(&COMPOSE.replace("worker-cache", "postgres"));
+        assert_eq!(
+            vec!["redis:6379", "redis:6380", "redis:6381", "postgres:5432"],
+            targets(&path, "worker").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_return_no_targets_without_dependencies() {
+        let path = write_file(COMPOSE);
+        assert!(targets(&path, "postgres").unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_fail_on_invalid_files_and_services() {
+        let path = write_file(COMPOSE);
+        assert!(targets(&path, "fsdfsdf").is_err());
+        assert!(targets(&path, "worker").is_err());
+        assert!(targets("./target/fsdfsdfwerwerwer.yml", "app").is_err());
+        assert!(targets(&write_file("version: '3'"), "app").is_err());
+        assert!(targets(&write_file("services: ["), "app").is_err());
+    }
+
+    #[test]
+    fn should_parse_the_container_ports() {
+        assert_eq!(Some(80), container_port(&Value::from(80)));
+        assert_eq!(Some(80), container_port(&Value::from("80")));
+        assert_eq!(Some(80), container_port(&Value::from("8080:80")));
+        assert_eq!(
+            Some(80),
+            container_port(&Value::from("127.0.0.1:8080:80/tcp"))
+        );
+        assert_eq!(
+            Some(8000),
+            container_port(&Value::from("9000-9010:8000-8010"))
+        );
+        assert_eq!(None, container_port(&Value::from("53:53/udp")));
+        assert_eq!(None, container_port(&Value::from("abc")));
+    }
+}
@@ -0,0 +1,308 @@
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+const ENV_PREFIX: &str = "WAIT_";
+const STAGES_KEY: &str = "stages";
+const STAGE_PREFIX: &str = "STAGE_";
+// Options whose arrays are the arguments of a command
+const COMMAND_KEYS: &[&str] = &[
+    "command",
+    "on_timeout_command",
+    "on_ready_command",
+    "on_target_change_command",
+];
+const TARGET_KEY: &str = "target";
+const MEMBERS_KEY: &str = "members";
+const GROUPS: &[&str] = &["any", "quorum"];
+const AT_LEAST_GROUP: &str = "atleast";
+
+// Loads a TOML, YAML or JSON configuration file and maps every entry to the environment variable
+// with the same name, e.g. `backoff_jitter = "full"` is equivalent to `WAIT_BACKOFF_JITTER=full`.
+pub fn load(path: &str) -> Result<Vec<(String, String)>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read configuration file [{}]: {}", path, e))?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let value: Value = match extension.as_str() {
+        "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+        "yaml" | "yml" => serde_yaml_ng::from_str(&content).map_err(|e| e.to_string()),
+        "json" => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        _ => Err("unsupported file extension, expected .toml, .yaml, .yml or .json".to_string()),
+    }
+    .map_err(|e| format!("cannot parse configuration file [{}]: {}", path, e))?;
+    to_env_vars(&value)
+}
+
+pub fn to_env_vars(value: &Value) -> Result<Vec<(String, String)>, String> {
+    let mut vars = vec![];
+    match value {
+        Value::Object(entries) => {
+            for (key, value) in entries {
+                if key == STAGES_KEY {
+                    vars.extend(stages_to_env_vars(value)?);
+                } else {
+                    vars.push((to_env_var_name("", key), to_env_var_value(key, value)?));
+                }
+            }
+        }
+        Value::Null => {}
+        _ => return Err("the configuration must be a map of options".to_string()),
+    }
+    Ok(vars)
+}
+
+fn stages_to_env_vars(stages: &Value) -> Result<Vec<(String, String)>, String> {
+    let mut vars = vec![];
+    match stages {
+        Value::Array(stages) => {
+            for (index, stage) in stages.iter().enumerate() {
+                let prefix = format!("{}{}_", STAGE_PREFIX, index + 1);
+                match stage {
+                    Value::Object(entries) => {
+                        for (key, value) in entries {
+                            vars.push((
+                                to_env_var_name(&prefix, key),
+                                to_env_var_value(key, value)?,
+                            ));
+                        }
+                    }
+                    _ => return Err(format!("stage {} must be a map of options", index + 1)),
+                }
+            }
+        }
+        _ => return Err(format!("[{}] must be a list of stages", STAGES_KEY)),
+    }
+    Ok(vars)
+}
+
+fn to_env_var_name(prefix: &str, key: &str) -> String {
+    format!(
+        "{}{}{}",
+        ENV_PREFIX,
+        prefix,
+        key.replace('-', "_").to_uppercase()
+    )
+}
+
+fn to_env_var_value(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::Array(args) if COMMAND_KEYS.contains(&key) => Ok(shell_words::join(
+            args.iter()
+                .map(|arg| to_scalar(key, arg))
+                .collect::<Result<Vec<String>, String>>()?,
+        )),
+        Value::Array(entries) => Ok(entries
+            .iter()
+            .map(|entry| to_target(key, entry))
+            .collect::<Result<Vec<String>, String>>()?
+            .join(", ")),
+        _ => to_scalar(key, value),
+    }
+}
+
+fn to_scalar(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        Value::Null => Ok("".to_string()),
+        _ => Err(format!("unsupported value for [{}]: {}", key, value)),
+    }
+}
+
+// A target is either a plain string or a map with the target and its options, e.g.
+// `{ target = "db:5432", timeout = 10 }`, `{ any = ["a:1", "b:1"] }` or `{ atleast = 2, members = ["a:1", "b:1", "c:1"] }`
+fn to_target(key: &str, value: &Value) -> Result<String, String> {
+    let entries = match value {
+        Value::Object(entries) => entries,
+        _ => return to_scalar(key, value),
+    };
+    let mut target = None;
+    let mut options = vec![];
+    for (name, value) in entries {
+        if name == TARGET_KEY {
+            target = Some(to_scalar(key, value)?);
+        } else if GROUPS.contains(&name.as_str()) {
+            target = Some(format!("{}({})", name, to_members(key, value)?));
+        } else if name == AT_LEAST_GROUP {
+            let members = entries
+                .get(MEMBERS_KEY)
+                .ok_or_else(|| format!("missing [{}] of group in [{}]", MEMBERS_KEY, key))?;
+            target = Some(format!(
+                "{}({}; {})",
+                name,
+                to_scalar(key, value)?,
+                to_members(key, members)?
+            ));
+        } else if name != MEMBERS_KEY {
+            options.push(option(key, name, value)?);
+        }
+    }
+    match target {
+        Some(target) if options.is_empty() => Ok(target),
+        Some(target) => Ok(format!("{}?{}", target, options.join("&"))),
+        None => Err(format!("missing [{}] in an entry of [{}]", TARGET_KEY, key)),
+    }
+}
+
+fn to_members(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::Array(members) => Ok(members
+            .iter()
+            .map(|member| to_target(key, member))
+            .collect::<Result<Vec<String>, String>>()?
+            .join(", ")),
+        _ => Err(format!(
+            "the members of a group in [{}] must be a list",
+            key
+        )),
+    }
+}
+
+fn option(key: &str, name: &str, value: &Value) -> Result<String, String> {
+    Ok(format!("{}={}", name, to_scalar(key, value)?))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    fn write_file(extension: &str, content: &str) -> String {
+        let path = format!("./target/{}.{}", rand::random::<u64>(), extension);
+        File::create(&path)
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap();
+        path
+    }
+
+    fn var<'a>(vars: &'a [(String, String)], key: &str) -> &'a str {
+        &vars.iter().find(|(name, _)| name == key).unwrap().1
+    }
+
+    #[test]
+    fn should_load_a_toml_file() {
+        let path = write_file(
+            "toml",
+            r#"
+timeout = 60
+backoff-jitter = "full"
+optional_flag = true
+hosts = ["db:5432", { target = "cache:6379", timeout = "10s", optional = true }]
+paths = "/tmp/a, /tmp/b"
+command = ["/app", "--name", "my app"]
+
+[[stages]]
+name = "vault"
+hosts = [{ quorum = ["vault1:8200", "vault2:8200", "vault3:8200"], timeout = 30 }]
+
+[[stages]]
+paths = [{ atleast = 1, members = ["/tmp/c", "/tmp/d"] }]
+after = 2
+"#,
+        );
+        let vars = load(&path).unwrap();
+
+        assert_eq!("60", var(&vars, "WAIT_TIMEOUT"));
+        assert_eq!("full", var(&vars, "WAIT_BACKOFF_JITTER"));
+        assert_eq!("true", var(&vars, "WAIT_OPTIONAL_FLAG"));
+        assert_eq!(
+            "db:5432, cache:6379?timeout=10s&optional=true",
+            var(&vars, "WAIT_HOSTS")
+        );
+        assert_eq!("/tmp/a, /tmp/b", var(&vars, "WAIT_PATHS"));
+        assert_eq!("/app --name 'my app'", var(&vars, "WAIT_COMMAND"));
+        assert_eq!("vault", var(&vars, "WAIT_STAGE_1_NAME"));
+        assert_eq!(
+            "quorum(vault1:8200, vault2:8200, vault3:8200)?timeout=30",
+            var(&vars, "WAIT_STAGE_1_HOSTS")
+        );
+        assert_eq!(
+            "atleast(1; /tmp/c, /tmp/d)",
+            var(&vars, "WAIT_STAGE_2_PATHS")
+        );
+        assert_eq!("2", var(&vars, "WAIT_STAGE_2_AFTER"));
+    }
+
+    #[test]
+    fn should_load_a_yaml_file() {
+        let path = write_file(
+            "yml",
+            r#"
+timeout: 60
+hosts:
+  - db:5432
+  - target: cache:6379
+    interval: 2
+stages:
+  - name: migrations
+    paths: [/tmp/done]
+"#,
+        );
+        let vars = load(&path).unwrap();
+
+        assert_eq!("60", var(&vars, "WAIT_TIMEOUT"));
+        assert_eq!("db:5432, cache:6379?interval=2", var(&vars, "WAIT_HOSTS"));
+        assert_eq!("migrations", var(&vars, "WAIT_STAGE_1_NAME"));
+        assert_eq!("/tmp/done", var(&vars, "WAIT_STAGE_1_PATHS"));
+    }
+
+    #[test]
+    fn should_load_a_json_file() {
+        let path = write_file(
+            "json",
+            r#"{ "timeout": 60, "hosts": [{ "any": ["a:1", "b:1"] }], "check_commands": ["pg_isready -h db"] }"#,
+        );
+        let vars = load(&path).unwrap();
+
+        assert_eq!("60", var(&vars, "WAIT_TIMEOUT"));
+        assert_eq!("any(a:1, b:1)", var(&vars, "WAIT_HOSTS"));
+        assert_eq!("pg_isready -h db", var(&vars, "WAIT_CHECK_COMMANDS"));
+    }
+
+    #[test]
+    fn should_fail_on_invalid_files() {
+        assert!(load("./target/fsdfsdfwerwerwer.toml").is_err());
+        assert!(load(&write_file("txt", "timeout = 1")).is_err());
+        assert!(load(&write_file("toml", "timeout = ")).is_err());
+        assert!(load(&write_file("json", "[1, 2]")).is_err());
+        assert!(load(&write_file("json", r#"{ "hosts": [{ "timeout": 1 }] }"#)).is_err());
+        assert!(load(&write_file("json", r#"{ "hosts": [{ "atleast": 1 }] }"#)).is_err());
+        assert!(load(&write_file("json", r#"{ "stages": { "hosts": "a:1" } }"#)).is_err());
+        assert!(load(&write_file("json", r#"{ "timeout": { "value": 1 } }"#)).is_err());
+    }
+
+    #[test]
+    fn should_load_an_empty_file() {
+        assert!(load(&write_file("yaml", "")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn schema_should_be_valid_json() {
+        let schema: Value =
+            serde_json::from_str(include_str!("../../schema/wait.schema.json")).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        for key in [
+            "hosts",
+            "paths",
+            "processes",
+            "check_commands",
+            "command",
+            "timeout",
+            "stages",
+        ] {
+            assert!(
+                properties.contains_key(key),
+                "missing [{}] in the schema",
+                key
+            );
+        }
+    }
+}
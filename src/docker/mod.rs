@@ -0,0 +1,143 @@
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+pub const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+// A container is available once its healthcheck passes or,
+// if it has no healthcheck, once it is running.
+pub fn check(socket: &str, container: &str, timeout: Duration) -> Result<(), String> {
+    let (status_line, body) = get(socket, &format!("/containers/{}/json", container), timeout)
+        .map_err(|e| format!("Docker socket [{}] not available: {}", socket, e))?;
+    let code = status_line.split_whitespace().nth(1).unwrap_or("");
+    let json: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+    match code {
+        "200" => {}
+        "404" => return Err("container not found".to_string()),
+        _ => {
+            return Err(format!(
+                "Docker API returned [{}]: {}",
+                status_line,
+                json["message"].as_str().unwrap_or("")
+            ));
+        }
+    }
+    let state = &json["State"];
+    if let Some(health) = state["Health"]["Status"].as_str() {
+        return match health {
+            "healthy" => Ok(()),
+            _ => Err(format!("container {}", health)),
+        };
+    }
+    match state["Running"].as_bool() {
+        Some(true) => Ok(()),
+        _ => Err(format!(
+            "container {}",
+            state["Status"].as_str().unwrap_or("in an unknown state")
+        )),
+    }
+}
+
+// HTTP/1.0 keeps the response free of chunked encoding and lets the Engine close the stream.
+// Returns the status line and the body.
+fn get(socket: &str, path: &str, timeout: Duration) -> std::io::Result<(String, String)> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    write!(stream, "GET {} HTTP/1.0\r\nHost: docker\r\n\r\n", path)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    Ok((
+        head.lines().next().unwrap_or("").to_string(),
+        body.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    // Answers with the inspect response of the containers, by name
+    fn fake_engine(containers: &'static [(&'static str, &'static str)]) -> String {
+        let socket = format!("./target/{}.sock", rand::random::<u64>());
+        let listener = UnixListener::bind(&socket).unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                BufReader::new(&stream)
+                    .read_line(&mut request_line)
+                    .unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap_or("");
+                let (status_line, body) = match containers
+                    .iter()
+                    .find(|(name, _)| path == format!("/containers/{}/json", name))
+                {
+                    Some((_, body)) => ("HTTP/1.0 200 OK", body.to_string()),
+                    None => (
+                        "HTTP/1.0 404 Not Found",
+                        format!(r#"{{"message":"No such container: {}"}}"#, path),
+                    ),
+                };
+                write!(
+                    stream,
+                    "{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    status_line,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        socket
+    }
+
+    #[test]
+    fn should_check_the_state_of_the_container() {
+        let socket = fake_engine(&[
+            (
+                "db",
+                r#"{"State":{"Status":"running","Running":true,"Health":{"Status":"healthy"}}}"#,
+            ),
+            (
+                "cache",
+                r#"{"State":{"Status":"running","Running":true,"Health":{"Status":"starting"}}}"#,
+            ),
+            ("app", r#"{"State":{"Status":"running","Running":true}}"#),
+            ("job", r#"{"State":{"Status":"exited","Running":false}}"#),
+        ]);
+
+        assert_eq!(Ok(()), check(&socket, "db", TIMEOUT));
+        assert_eq!(
+            Err("container starting".to_string()),
+            check(&socket, "cache", TIMEOUT)
+        );
+        assert_eq!(Ok(()), check(&socket, "app", TIMEOUT));
+        assert_eq!(
+            Err("container exited".to_string()),
+            check(&socket, "job", TIMEOUT)
+        );
+        assert_eq!(
+            Err("container not found".to_string()),
+            check(&socket, "other", TIMEOUT)
+        );
+    }
+
+    #[test]
+    fn should_fail_if_the_socket_is_not_available() {
+        let result = check("./target/fsdfsdfwerwer.sock", "db", TIMEOUT);
+        assert!(
+            result
+                .unwrap_err()
+                .starts_with("Docker socket [./target/fsdfsdfwerwer.sock] not available")
+        );
+    }
+}
@@ -1,24 +1,197 @@
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
+use std::fs;
+use std::sync::{LazyLock, RwLock};
+
+const FILE_SUFFIX: &str = "_FILE";
+pub const DEFAULT_PREFIX: &str = "WAIT_";
+
+static PREFIX: LazyLock<RwLock<String>> = LazyLock::new(|| RwLock::new(DEFAULT_PREFIX.to_string()));
+static OVERRIDES: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+static FILE_VARS: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
 
 pub fn env_var(key: &str, default: String) -> String {
-    match env::var(key) {
-        Ok(val) => val,
-        Err(_e) => default,
+    try_env_var(key, default).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// Following the Docker secrets convention, the value of every variable can also be read
+// from the file whose path is set in the variable with the same name and the `_FILE` suffix.
+// The variable and its file variable are looked up together in every source, by precedence,
+// so they only conflict when both are set in the same source.
+pub fn try_env_var(key: &str, default: String) -> Result<String, String> {
+    let secret_key = format!("{}{}", key, FILE_SUFFIX);
+    for lookup in SOURCES {
+        match (lookup(key), lookup(&secret_key)) {
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "Both [{}] and [{}] are set, only one of them is allowed",
+                    key, secret_key
+                ));
+            }
+            (Some(val), None) => return Ok(val),
+            (None, Some(path)) => {
+                return fs::read_to_string(&path)
+                    .map(|content| content.trim_end_matches(['\n', '\r']).to_string())
+                    .map_err(|e| format!("Cannot read [{}] from file [{}]: {}", key, path, e));
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(default)
+}
+
+// The sources of the variables, from the highest precedence
+const SOURCES: [fn(&str) -> Option<String>; 3] = [override_var, prefixed_env_var, file_var];
+
+// Where the value of a variable comes from
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    Default,
+    Override,
+    Env(String),
+    SecretFile(String),
+    ConfigFile,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Override => write!(f, "command line"),
+            Source::Env(name) => write!(f, "environment variable {}", name),
+            Source::SecretFile(name) => write!(f, "file from environment variable {}", name),
+            Source::ConfigFile => write!(f, "configuration file"),
+        }
+    }
+}
+
+pub fn source(key: &str) -> Source {
+    let secret_key = format!("{}{}", key, FILE_SUFFIX);
+    if override_var(key).is_some() {
+        Source::Override
+    } else if override_var(&secret_key).is_some() {
+        Source::SecretFile(secret_key)
+    } else if env::var(to_prefixed_name(&prefix(), key)).is_ok() {
+        Source::Env(to_prefixed_name(&prefix(), key))
+    } else if env::var(to_prefixed_name(&prefix(), &secret_key)).is_ok() {
+        Source::SecretFile(to_prefixed_name(&prefix(), &secret_key))
+    } else if file_var(key).is_some() || file_var(&secret_key).is_some() {
+        Source::ConfigFile
+    } else {
+        Source::Default
     }
 }
 
 pub fn env_var_exists(key: &str) -> bool {
-    env::var(key).is_ok()
+    raw_var(key).is_some() || raw_var(&format!("{}{}", key, FILE_SUFFIX)).is_some()
+}
+
+fn raw_var(key: &str) -> Option<String> {
+    SOURCES.iter().find_map(|lookup| lookup(key))
+}
+
+fn prefixed_env_var(key: &str) -> Option<String> {
+    env::var(to_prefixed_name(&prefix(), key)).ok()
+}
+
+pub fn env_var_names() -> Vec<String> {
+    let prefix = prefix();
+    let mut names: Vec<String> = env::vars_os()
+        .filter_map(|(key, _)| key.into_string().ok())
+        .filter_map(|key| from_prefixed_name(&prefix, key))
+        .chain(
+            OVERRIDES
+                .read()
+                .expect("env overrides lock poisoned")
+                .keys()
+                .cloned(),
+        )
+        .chain(
+            FILE_VARS
+                .read()
+                .expect("file vars lock poisoned")
+                .keys()
+                .cloned(),
+        )
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+// The variables of the environment are read with the given prefix in place of `WAIT_`,
+// e.g. with the `MIGRATE_WAIT_` prefix, `WAIT_HOSTS` is read from `MIGRATE_WAIT_HOSTS`.
+// Overrides and file variables are not affected.
+pub fn set_prefix(prefix: &str) {
+    *PREFIX.write().expect("env prefix lock poisoned") = prefix.to_string();
+}
+
+fn prefix() -> String {
+    PREFIX.read().expect("env prefix lock poisoned").clone()
+}
+
+fn to_prefixed_name(prefix: &str, key: &str) -> String {
+    match key.strip_prefix(DEFAULT_PREFIX) {
+        Some(name) => format!("{}{}", prefix, name),
+        None => key.to_string(),
+    }
+}
+
+// Variables with the default prefix are ignored when a different prefix is set
+fn from_prefixed_name(prefix: &str, key: String) -> Option<String> {
+    if prefix == DEFAULT_PREFIX {
+        return Some(key);
+    }
+    match key.strip_prefix(prefix) {
+        Some(name) => Some(format!("{}{}", DEFAULT_PREFIX, name)),
+        None if key.starts_with(DEFAULT_PREFIX) => None,
+        None => Some(key),
+    }
+}
+
+// Overrides take precedence over the environment variables (e.g. values from the command line)
+pub fn set_override(key: &str, value: String) {
+    OVERRIDES
+        .write()
+        .expect("env overrides lock poisoned")
+        .insert(key.to_string(), value);
+}
+
+// File variables have the lowest precedence (e.g. values from a configuration file)
+// and replace the previously set ones
+pub fn set_file_vars(vars: Vec<(String, String)>) {
+    *FILE_VARS.write().expect("file vars lock poisoned") = vars.into_iter().collect();
+}
+
+fn file_var(key: &str) -> Option<String> {
+    FILE_VARS
+        .read()
+        .expect("file vars lock poisoned")
+        .get(key)
+        .cloned()
+}
+
+fn override_var(key: &str) -> Option<String> {
+    OVERRIDES
+        .read()
+        .expect("env overrides lock poisoned")
+        .get(key)
+        .cloned()
 }
 
 #[cfg(test)]
 mod test {
 
     use super::*;
+    use crate::test::TEST_MUTEX;
     use std::env;
 
     #[test]
     fn should_return_an_env_variable() {
+        let _guard = TEST_MUTEX.lock().unwrap();
         let mut env_key = String::from("");
         let mut env_value = String::from("");
 
@@ -46,4 +219,180 @@ mod test {
         assert!(!env_var_exists(&env_key));
         assert_eq!(random.to_string(), env_var(&env_key, random.to_string()));
     }
+
+    #[test]
+    fn should_return_the_override_value_if_present() {
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_OVERRIDE_{}", random);
+
+        assert!(!env_var_exists(&env_key));
+
+        set_override(&env_key, "overridden".to_string());
+
+        assert!(env_var_exists(&env_key));
+        assert_eq!("overridden", env_var(&env_key, "".to_string()));
+    }
+
+    #[test]
+    fn should_return_the_names_of_env_variables_and_overrides() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_NAMES_{}", random);
+        assert!(!env_var_names().contains(&env_key));
+
+        set_override(&env_key, "".to_string());
+
+        let names = env_var_names();
+        assert!(names.contains(&env_key));
+        assert!(names.contains(&env::vars().next().unwrap().0));
+    }
+
+    #[test]
+    fn should_return_the_file_value_if_env_variable_not_present() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_FILE_{}", random);
+        let (key, value) = env::vars().next().unwrap();
+
+        set_file_vars(vec![
+            (env_key.clone(), "from file".to_string()),
+            (key.clone(), "from file".to_string()),
+        ]);
+
+        assert!(env_var_exists(&env_key));
+        assert_eq!("from file", env_var(&env_key, "".to_string()));
+        assert_eq!(value, env_var(&key, "".to_string()));
+        assert!(env_var_names().contains(&env_key));
+
+        set_file_vars(vec![]);
+        assert!(!env_var_exists(&env_key));
+    }
+
+    #[test]
+    fn should_return_the_source_of_a_variable() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_SOURCE_{}", random);
+        let (key, _) = env::vars().next().unwrap();
+
+        assert_eq!(Source::Default, source(&env_key));
+        assert_eq!(Source::Env(key.clone()), source(&key));
+
+        set_override(
+            &format!("{}_FILE", env_key),
+            "/run/secrets/source".to_string(),
+        );
+        assert_eq!(
+            Source::SecretFile(format!("{}_FILE", env_key)),
+            source(&env_key)
+        );
+
+        set_override(&env_key, "".to_string());
+        assert_eq!(Source::Override, source(&env_key));
+    }
+
+    #[test]
+    fn should_map_names_to_the_prefix() {
+        assert_eq!("WAIT_HOSTS", to_prefixed_name(DEFAULT_PREFIX, "WAIT_HOSTS"));
+        assert_eq!(
+            "MIGRATE_WAIT_HOSTS",
+            to_prefixed_name("MIGRATE_WAIT_", "WAIT_HOSTS")
+        );
+        assert_eq!("HOME", to_prefixed_name("MIGRATE_WAIT_", "HOME"));
+
+        assert_eq!(
+            Some("WAIT_HOSTS".to_string()),
+            from_prefixed_name(DEFAULT_PREFIX, "WAIT_HOSTS".to_string())
+        );
+        assert_eq!(
+            Some("WAIT_HOSTS".to_string()),
+            from_prefixed_name("WAIT_MIGRATE_", "WAIT_MIGRATE_HOSTS".to_string())
+        );
+        assert_eq!(
+            None,
+            from_prefixed_name("WAIT_MIGRATE_", "WAIT_HOSTS".to_string())
+        );
+        assert_eq!(
+            Some("HOME".to_string()),
+            from_prefixed_name("WAIT_MIGRATE_", "HOME".to_string())
+        );
+    }
+
+    #[test]
+    fn should_return_the_value_from_the_file_of_the_file_variable() {
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_SECRET_{}", random);
+        let path = format!("./target/{}.secret", random);
+        fs::write(&path, "secret value\n").unwrap();
+
+        assert!(!env_var_exists(&env_key));
+
+        set_override(&format!("{}_FILE", env_key), path);
+
+        assert!(env_var_exists(&env_key));
+        assert_eq!("secret value", env_var(&env_key, "".to_string()));
+    }
+
+    #[test]
+    fn should_fail_if_the_file_variable_cannot_be_read() {
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_SECRET_{}", random);
+
+        set_override(
+            &format!("{}_FILE", env_key),
+            "./target/fsdfsdfwerwerwer".to_string(),
+        );
+
+        assert!(try_env_var(&env_key, "".to_string()).is_err());
+    }
+
+    #[test]
+    fn should_fail_if_both_the_variable_and_the_file_variable_are_set() {
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_SECRET_{}", random);
+        let path = format!("./target/{}.secret", random);
+        fs::write(&path, "secret value").unwrap();
+
+        set_override(&env_key, "value".to_string());
+        set_override(&format!("{}_FILE", env_key), path);
+
+        assert!(try_env_var(&env_key, "".to_string()).is_err());
+    }
+
+    #[test]
+    fn should_prefer_the_command_line_to_the_file_variable_of_the_environment() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_SECRET_{}", random);
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var(format!("{}_FILE", env_key), "./target/fsdfsdfwerwerwer") };
+        set_override(&env_key, "from command line".to_string());
+
+        assert_eq!(
+            Ok("from command line".to_string()),
+            try_env_var(&env_key, "".to_string())
+        );
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var(format!("{}_FILE", env_key)) };
+    }
+
+    #[test]
+    fn should_prefer_the_file_variable_of_the_environment_to_the_config_file() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let random: i64 = rand::random();
+        let env_key = format!("WAIT_SECRET_{}", random);
+        let path = format!("./target/{}.secret", random);
+        fs::write(&path, "secret value").unwrap();
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var(format!("{}_FILE", env_key), &path) };
+        set_file_vars(vec![(env_key.clone(), "from file".to_string())]);
+
+        let value = try_env_var(&env_key, "".to_string());
+        set_file_vars(vec![]);
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var(format!("{}_FILE", env_key)) };
+        assert_eq!(Ok("secret value".to_string()), value);
+    }
 }
@@ -0,0 +1,49 @@
+// Exit codes of the wait process, following the shell conventions where they exist
+pub const TIMEOUT: i32 = 1;
+pub const INVALID_CONFIGURATION: i32 = 2;
+// Docker reserves 2 in a HEALTHCHECK, so the check subcommand reports everything else as unhealthy
+pub const UNHEALTHY: i32 = 1;
+pub const COMMAND_NOT_EXECUTABLE: i32 = 126;
+pub const COMMAND_NOT_FOUND: i32 = 127;
+// Added to the number of the signal that interrupted the wait or the command
+pub const SIGNAL_BASE: i32 = 128;
+
+pub fn from_exec_error(error: &exec::Error) -> i32 {
+    match error {
+        exec::Error::Errno(errno) if errno.0 == libc::ENOENT => COMMAND_NOT_FOUND,
+        _ => COMMAND_NOT_EXECUTABLE,
+    }
+}
+
+pub fn from_spawn_error(error: &std::io::Error) -> i32 {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => COMMAND_NOT_FOUND,
+        _ => COMMAND_NOT_EXECUTABLE,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_map_exec_errors() {
+        let error = exec::Command::new("fsdfsdfsdfwerwer").exec();
+        assert_eq!(COMMAND_NOT_FOUND, from_exec_error(&error));
+
+        let error = exec::Command::new("./src").exec();
+        assert_eq!(COMMAND_NOT_EXECUTABLE, from_exec_error(&error));
+    }
+
+    #[test]
+    fn should_map_spawn_errors() {
+        let error = std::process::Command::new("fsdfsdfsdfwerwer")
+            .spawn()
+            .unwrap_err();
+        assert_eq!(COMMAND_NOT_FOUND, from_spawn_error(&error));
+
+        let error = std::process::Command::new("./src").spawn().unwrap_err();
+        assert_eq!(COMMAND_NOT_EXECUTABLE, from_spawn_error(&error));
+    }
+}
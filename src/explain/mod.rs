@@ -0,0 +1,389 @@
+use crate::env_reader::{self, Source};
+use crate::target::{self, TargetSpec};
+use crate::{Config, backoff, watch};
+use serde_json::{Value, json};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+pub fn format_from_str(format: &str) -> Option<Format> {
+    match format.trim().to_lowercase().as_str() {
+        "" | "0" | "false" | "no" => None,
+        "json" => Some(Format::Json),
+        _ => Some(Format::Text),
+    }
+}
+
+struct Entry {
+    name: &'static str,
+    value: Value,
+    source: String,
+}
+
+// Describes the fully resolved configuration, with the source of every value
+pub fn explain(config: &Config, format: Format) -> Result<String, String> {
+    let entries = entries(config);
+    let targets = target::from_config(config)?;
+    let mut stages = vec![];
+    for stage in &config.stages {
+        stages.push((stage, target::from_stage(stage, config)?));
+    }
+    Ok(match format {
+        Format::Json => json!({
+            "options": entries
+                .iter()
+                .map(|entry| (entry.name.to_string(), json!({ "value": entry.value, "source": entry.source })))
+                .collect::<serde_json::Map<String, Value>>(),
+            "targets": targets.iter().map(target_to_json).collect::<Vec<Value>>(),
+            "stages": stages
+                .iter()
+                .map(|(stage, targets)| json!({
+                    "name": stage.name,
+                    "timeout": stage.timeout,
+                    "before": stage.wait_before,
+                    "after": stage.wait_after,
+                    "targets": targets.iter().map(target_to_json).collect::<Vec<Value>>(),
+                }))
+                .collect::<Vec<Value>>(),
+        })
+        .to_string(),
+        Format::Text => {
+            let mut lines = vec!["Options:".to_string()];
+            for entry in &entries {
+                let value = match &entry.value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => "none".to_string(),
+                    value => value.to_string(),
+                };
+                lines.push(format!(" - {}: [{}] from {}", entry.name, value, entry.source));
+            }
+            lines.push("Targets:".to_string());
+            lines.extend(targets.iter().map(target_to_text));
+            for (stage, targets) in &stages {
+                lines.push(format!(
+                    "Stage [{}], timeout {}, sleeping time before {} and after {} seconds:",
+                    stage.name,
+                    stage.timeout_to_string(config.global_timeout),
+                    stage.wait_before,
+                    stage.wait_after
+                ));
+                lines.extend(targets.iter().map(target_to_text));
+            }
+            lines.join("\n")
+        }
+    })
+}
+
+fn entries(config: &Config) -> Vec<Entry> {
+    let (backoff, multiplier, max_interval) = match config.backoff.strategy {
+        backoff::Strategy::Fixed => ("fixed", None, None),
+        backoff::Strategy::Exponential {
+            multiplier,
+            max_interval,
+        } => ("exponential", Some(multiplier), Some(max_interval)),
+    };
+    let (watch, watch_signal, watch_interval, watch_failure_threshold) = match &config.watch {
+        Some(watch) => (
+            match watch.action {
+                watch::Action::Log => "log",
+                watch::Action::Signal(_) => "signal",
+                watch::Action::Terminate => "terminate",
+            },
+            match watch.action {
+                watch::Action::Signal(signal) => Some(signal),
+                _ => None,
+            },
+            Some(watch.interval),
+            Some(watch.failure_threshold),
+        ),
+        None => ("none", None, None, None),
+    };
+    let mut hosts_source = source(&["WAIT_HOSTS"]);
+    // Already validated while reading the configuration
+    if !env_reader::try_env_var("WAIT_COMPOSE_FILE", "".to_string())
+        .unwrap_or_default()
+        .trim()
+        .is_empty()
+    {
+        hosts_source = format!("{} and compose file", hosts_source);
+    }
+    vec![
+        entry("hosts", json!(config.hosts), hosts_source),
+        entry("paths", json!(config.paths), source(&["WAIT_PATHS"])),
+        entry(
+            "processes",
+            json!(config.processes),
+            source(&["WAIT_PROCESSES"]),
+        ),
+        entry(
+            "check_commands",
+            json!(config.check_commands),
+            source(&["WAIT_CHECK_COMMANDS"]),
+        ),
+        entry(
+            "command",
+            json!(config.command.as_ref().map(|(_, command)| command)),
+            source(&["WAIT_COMMAND"]),
+        ),
+        entry(
+            "timeout",
+            json!(config.global_timeout),
+            source(&["WAIT_TIMEOUT", "WAIT_HOSTS_TIMEOUT"]),
+        ),
+        entry(
+            "host_connect_timeout",
+            json!(config.tcp_connection_timeout),
+            source(&["WAIT_HOST_CONNECT_TIMEOUT"]),
+        ),
+        entry(
+            "before",
+            json!(config.wait_before),
+            source(&["WAIT_BEFORE", "WAIT_BEFORE_HOSTS"]),
+        ),
+        entry(
+            "after",
+            json!(config.wait_after),
+            source(&["WAIT_AFTER", "WAIT_AFTER_HOSTS"]),
+        ),
+        entry(
+            "sleep_interval",
+            json!(config.wait_sleep_interval),
+            source(&["WAIT_SLEEP_INTERVAL"]),
+        ),
+        entry("backoff", json!(backoff), source(&["WAIT_BACKOFF"])),
+        entry(
+            "backoff_multiplier",
+            json!(multiplier),
+            source(&["WAIT_BACKOFF_MULTIPLIER"]),
+        ),
+        entry(
+            "backoff_max_interval",
+            json!(max_interval),
+            source(&["WAIT_BACKOFF_MAX_INTERVAL"]),
+        ),
+        entry(
+            "backoff_jitter",
+            json!(config.backoff.jitter.to_string()),
+            source(&["WAIT_BACKOFF_JITTER"]),
+        ),
+        entry(
+            "success_threshold",
+            json!(config.success_threshold),
+            source(&["WAIT_SUCCESS_THRESHOLD"]),
+        ),
+        entry(
+            "check_command_timeout",
+            json!(config.check_command_timeout),
+            source(&["WAIT_CHECK_COMMAND_TIMEOUT"]),
+        ),
+        entry(
+            "check_command_exit_code",
+            json!(config.check_command_exit_code),
+            source(&["WAIT_CHECK_COMMAND_EXIT_CODE"]),
+        ),
+        entry(
+            "docker_socket",
+            json!(config.docker_socket),
+            source(&["WAIT_DOCKER_SOCKET"]),
+        ),
+        entry(
+            "k8s_api_server",
+            json!(config.kubernetes.api_server),
+            source(&["WAIT_K8S_API_SERVER"]),
+        ),
+        entry(
+            "k8s_token_file",
+            json!(config.kubernetes.token_file),
+            source(&["WAIT_K8S_TOKEN_FILE"]),
+        ),
+        entry(
+            "k8s_ca_file",
+            json!(config.kubernetes.ca_file),
+            source(&["WAIT_K8S_CA_FILE"]),
+        ),
+        entry(
+            "on_timeout_command",
+            json!(config.hooks.on_timeout.as_ref().map(|(_, command)| command)),
+            source(&["WAIT_ON_TIMEOUT_COMMAND"]),
+        ),
+        entry(
+            "on_ready_command",
+            json!(config.hooks.on_ready.as_ref().map(|(_, command)| command)),
+            source(&["WAIT_ON_READY_COMMAND"]),
+        ),
+        entry(
+            "on_target_change_command",
+            json!(
+                config
+                    .hooks
+                    .on_target_change
+                    .as_ref()
+                    .map(|(_, command)| command)
+            ),
+            source(&["WAIT_ON_TARGET_CHANGE_COMMAND"]),
+        ),
+        entry(
+            "hook_timeout",
+            json!(config.hooks.timeout),
+            source(&["WAIT_HOOK_TIMEOUT"]),
+        ),
+        entry(
+            "marker_file",
+            json!(config.marker.as_ref().map(|marker| &marker.path)),
+            source(&["WAIT_MARKER_FILE"]),
+        ),
+        entry(
+            "marker_content",
+            json!(config.marker.as_ref().map(|marker| &marker.content)),
+            source(&["WAIT_MARKER_CONTENT"]),
+        ),
+        entry(
+            "timeout_exit_code",
+            json!(config.timeout_exit_code),
+            source(&["WAIT_TIMEOUT_EXIT_CODE"]),
+        ),
+        entry(
+            "status_addr",
+            json!(config.status_addr),
+            source(&["WAIT_STATUS_ADDR"]),
+        ),
+        entry(
+            "barrier_addr",
+            json!(config.barrier_addr),
+            source(&["WAIT_BARRIER_ADDR"]),
+        ),
+        entry("watch", json!(watch), source(&["WAIT_WATCH"])),
+        entry(
+            "watch_signal",
+            json!(watch_signal),
+            source(&["WAIT_WATCH_SIGNAL"]),
+        ),
+        entry(
+            "watch_interval",
+            json!(watch_interval),
+            source(&["WAIT_WATCH_INTERVAL"]),
+        ),
+        entry(
+            "watch_failure_threshold",
+            json!(watch_failure_threshold),
+            source(&["WAIT_WATCH_FAILURE_THRESHOLD"]),
+        ),
+        entry(
+            "watch_kill_timeout",
+            json!(config.watch.map(|watch| watch.kill_timeout)),
+            source(&["WAIT_WATCH_KILL_TIMEOUT"]),
+        ),
+    ]
+}
+
+fn entry(name: &'static str, value: Value, source: String) -> Entry {
+    Entry {
+        name,
+        value,
+        source,
+    }
+}
+
+// The first variable takes precedence over the following deprecated ones
+fn source(keys: &[&str]) -> String {
+    for (index, key) in keys.iter().enumerate() {
+        match env_reader::source(key) {
+            Source::Default => continue,
+            source if index > 0 => return format!("{} (deprecated)", source),
+            source => return source.to_string(),
+        }
+    }
+    Source::Default.to_string()
+}
+
+fn target_to_json(spec: &TargetSpec) -> Value {
+    json!({
+        "kind": spec.target.kind(),
+        "name": spec.target.name(),
+        "timeout": spec.timeout,
+        "interval": spec.interval,
+        "success_threshold": spec.success_threshold,
+        "optional": spec.optional,
+    })
+}
+
+fn target_to_text(spec: &TargetSpec) -> String {
+    format!(
+        " - {}: timeout {}, interval {}, success threshold {}{}",
+        spec,
+        spec.timeout,
+        spec.interval,
+        spec.success_threshold,
+        if spec.optional { ", optional" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::Stage;
+
+    fn config() -> Config {
+        Config {
+            hosts: "db:5432, cache:6379?optional&timeout=10".to_string(),
+            paths: "/tmp/a".to_string(),
+            stages: vec![Stage {
+                name: "vault".to_string(),
+                hosts: "vault:8200".to_string(),
+                timeout: Some(20),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_parse_the_format() {
+        assert_eq!(None, format_from_str(""));
+        assert_eq!(None, format_from_str("false"));
+        assert_eq!(Some(Format::Text), format_from_str("1"));
+        assert_eq!(Some(Format::Text), format_from_str("true"));
+        assert_eq!(Some(Format::Json), format_from_str(" JSON "));
+    }
+
+    #[test]
+    fn should_explain_the_config_as_text() {
+        let text = explain(&config(), Format::Text).unwrap();
+        assert!(text.contains(" - paths: [/tmp/a] from "));
+        assert!(text.contains(" - command: [none] from "));
+        assert!(text.contains(" - host [db:5432]: timeout 30, interval 1, success threshold 1\n"));
+        assert!(text.contains(
+            " - host [cache:6379]: timeout 10, interval 1, success threshold 1, optional\n"
+        ));
+        assert!(text.contains("Stage [vault], timeout 20 seconds, sleeping time"));
+        assert!(text.contains(" - host [vault:8200]: timeout 20"));
+    }
+
+    #[test]
+    fn should_explain_the_config_as_json() {
+        let json: Value = serde_json::from_str(&explain(&config(), Format::Json).unwrap()).unwrap();
+        assert_eq!("/tmp/a", json["options"]["paths"]["value"]);
+        assert_eq!(30, json["options"]["timeout"]["value"]);
+        assert_eq!("fixed", json["options"]["backoff"]["value"]);
+        assert_eq!("none", json["options"]["watch"]["value"]);
+        assert_eq!(3, json["targets"].as_array().unwrap().len());
+        assert_eq!("cache:6379", json["targets"][1]["name"]);
+        assert_eq!(true, json["targets"][1]["optional"]);
+        assert_eq!("path", json["targets"][2]["kind"]);
+        assert_eq!("vault", json["stages"][0]["name"]);
+        assert_eq!(20, json["stages"][0]["targets"][0]["timeout"]);
+    }
+
+    #[test]
+    fn should_fail_on_invalid_targets() {
+        let config = Config {
+            hosts: "atleast(a:1, b:1)".to_string(),
+            ..Default::default()
+        };
+        assert!(explain(&config, Format::Text).is_err());
+    }
+}
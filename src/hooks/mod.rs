@@ -0,0 +1,155 @@
+use crate::target::TargetSpec;
+use crate::{Command, command};
+use log::*;
+use std::time::Duration;
+
+pub const TIMEOUT: &str = "timeout";
+pub const READY: &str = "ready";
+pub const AVAILABLE: &str = "available";
+pub const UNAVAILABLE: &str = "unavailable";
+pub const SKIPPED: &str = "skipped";
+pub const TIMED_OUT: &str = "timed_out";
+
+// Commands run when the wait times out, once everything is ready,
+// and whenever the state of a target changes
+#[derive(Clone)]
+pub struct Hooks {
+    pub on_timeout: Option<(Command, String)>,
+    pub on_ready: Option<(Command, String)>,
+    pub on_target_change: Option<(Command, String)>,
+    pub timeout: u64,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Hooks {
+            on_timeout: None,
+            on_ready: None,
+            on_target_change: None,
+            timeout: 30,
+        }
+    }
+}
+
+pub struct Event {
+    pub name: &'static str,
+    pub target: String,
+    pub kind: &'static str,
+    pub reason: String,
+}
+
+impl Event {
+    pub fn new(name: &'static str, reason: &str) -> Event {
+        Event {
+            name,
+            target: "".to_string(),
+            kind: "",
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn for_target(name: &'static str, spec: &TargetSpec, reason: &str) -> Event {
+        Event {
+            name,
+            target: spec.target.name().to_string(),
+            kind: spec.target.kind(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Hooks {
+    // The event is described to the command by environment variables.
+    // Failures are only logged, as the hooks do not change the outcome of the wait.
+    pub fn run(&self, event: &Event, elapsed: u64) {
+        let hook = match event.name {
+            TIMEOUT => &self.on_timeout,
+            READY => &self.on_ready,
+            _ => &self.on_target_change,
+        };
+        let (command, command_string) = match hook {
+            Some(hook) => hook,
+            None => return,
+        };
+        info!(
+            "Running hook [{}] for event [{}] {}",
+            command_string, event.name, event.target
+        );
+        let env = [
+            ("WAIT_EVENT", event.name.to_string()),
+            ("WAIT_EVENT_TARGET", event.target.clone()),
+            ("WAIT_EVENT_KIND", event.kind.to_string()),
+            ("WAIT_EVENT_ELAPSED", elapsed.to_string()),
+            ("WAIT_EVENT_REASON", event.reason.clone()),
+        ];
+        match command::run_with_env(command, &env, Duration::from_secs(self.timeout)) {
+            Ok(outcome) if outcome.exit_code == Some(0) => {
+                debug!(
+                    "Hook [{}] completed. Output: [{}]",
+                    command_string, outcome.output
+                );
+            }
+            Ok(outcome) if outcome.timed_out => warn!(
+                "Hook [{}] killed after {} seconds. Output: [{}]",
+                command_string, self.timeout, outcome.output
+            ),
+            Ok(outcome) => warn!(
+                "Hook [{}] exited with code {:?}. Output: [{}]",
+                command_string, outcome.exit_code, outcome.output
+            ),
+            Err(e) => warn!("Hook [{}] failed to start: {}", command_string, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::{Config, parse_command, target};
+
+    fn hook(raw_cmd: &str) -> Option<(Command, String)> {
+        parse_command(raw_cmd).unwrap()
+    }
+
+    #[test]
+    fn should_run_the_hook_of_the_event() {
+        let path = format!("./target/{}.hook", rand::random::<u64>());
+        let hooks = Hooks {
+            on_timeout: hook(&format!(
+                "sh -c 'echo $WAIT_EVENT:$WAIT_EVENT_KIND:$WAIT_EVENT_TARGET:$WAIT_EVENT_ELAPSED:$WAIT_EVENT_REASON > {}'",
+                path
+            )),
+            on_ready: hook(&format!("sh -c 'echo $WAIT_EVENT >> {}'", path)),
+            ..Default::default()
+        };
+        let config = Config {
+            hosts: "db:5432".to_string(),
+            ..Default::default()
+        };
+        let specs = target::from_config(&config).unwrap();
+
+        hooks.run(
+            &Event::for_target(TIMEOUT, &specs[0], "connection refused"),
+            12,
+        );
+        hooks.run(&Event::new(READY, ""), 13);
+        hooks.run(&Event::for_target(AVAILABLE, &specs[0], ""), 14);
+
+        assert_eq!(
+            "timeout:host:db:5432:12:connection refused\nready\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_ignore_failing_hooks() {
+        let hooks = Hooks {
+            on_ready: hook("fsdfsdfsdfwerwer"),
+            on_target_change: hook("false"),
+            ..Default::default()
+        };
+        hooks.run(&Event::new(READY, ""), 0);
+        hooks.run(&Event::new(SKIPPED, ""), 0);
+    }
+}
@@ -0,0 +1,136 @@
+const MODIFIER_SEPARATOR: char = ':';
+const DEFAULT_MODIFIER: char = '-';
+const ERROR_MODIFIER: char = '?';
+
+// Expands `${VAR}`, `${VAR:-default}` and `${VAR:?error}` in the same way a shell would.
+// `$$` is replaced by a literal `$`, while any other `$` is kept as it is.
+pub fn interpolate<F: Fn(&str) -> Option<String>>(
+    value: &str,
+    lookup: F,
+) -> Result<String, String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("Missing closing brace in [{}]", value))?;
+            result.push_str(&expand(&after[..end], &lookup)?);
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn expand<F: Fn(&str) -> Option<String>>(expression: &str, lookup: &F) -> Result<String, String> {
+    let (name, fallback) = match expression.split_once(MODIFIER_SEPARATOR) {
+        Some((name, modifier)) => match modifier.strip_prefix(DEFAULT_MODIFIER) {
+            Some(default) => (name, Some(Ok(default))),
+            None => match modifier.strip_prefix(ERROR_MODIFIER) {
+                Some(error) => (name, Some(Err(error))),
+                None => return Err(format!("Invalid expression [${{{}}}]", expression)),
+            },
+        },
+        None => (expression, None),
+    };
+    if !is_valid_name(name) {
+        return Err(format!("Invalid variable name [{}]", name));
+    }
+    let value = lookup(name);
+    match fallback {
+        None => Ok(value.unwrap_or_default()),
+        Some(fallback) => match value.filter(|value| !value.is_empty()) {
+            Some(value) => Ok(value),
+            None => fallback
+                .map(|default| default.to_string())
+                .map_err(|error| format!("Variable [{}] is not set. {}", name, error)),
+        },
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "PORT" => Some("8080".to_string()),
+            "HOST" => Some("db".to_string()),
+            "EMPTY" => Some("".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn should_return_values_without_variables_unchanged() {
+        assert_eq!("", interpolate("", lookup).unwrap());
+        assert_eq!(
+            "/app --port 80",
+            interpolate("/app --port 80", lookup).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_expand_variables() {
+        assert_eq!(
+            "/app --port 8080",
+            interpolate("/app --port ${PORT}", lookup).unwrap()
+        );
+        assert_eq!("db:8080", interpolate("${HOST}:${PORT}", lookup).unwrap());
+        assert_eq!("db:", interpolate("${HOST}:${MISSING}", lookup).unwrap());
+    }
+
+    #[test]
+    fn should_expand_default_values() {
+        assert_eq!(
+            "db:5432",
+            interpolate("${HOST:-localhost}:${DB_PORT:-5432}", lookup).unwrap()
+        );
+        assert_eq!("empty", interpolate("${EMPTY:-empty}", lookup).unwrap());
+        assert_eq!("", interpolate("${MISSING:-}", lookup).unwrap());
+        assert_eq!("a:-b", interpolate("${MISSING:-a:-b}", lookup).unwrap());
+    }
+
+    #[test]
+    fn should_fail_on_required_variables() {
+        assert_eq!(
+            "8080",
+            interpolate("${PORT:?port is required}", lookup).unwrap()
+        );
+        let error = interpolate("${MISSING:?port is required}", lookup).unwrap_err();
+        assert!(error.contains("MISSING"));
+        assert!(error.contains("port is required"));
+        assert!(interpolate("${EMPTY:?}", lookup).is_err());
+    }
+
+    #[test]
+    fn should_keep_escaped_and_lone_dollars() {
+        assert_eq!("${PORT}", interpolate("$${PORT}", lookup).unwrap());
+        assert_eq!("$$", interpolate("$$$$", lookup).unwrap());
+        assert_eq!("echo $PORT $", interpolate("echo $PORT $", lookup).unwrap());
+    }
+
+    #[test]
+    fn should_fail_on_invalid_expressions() {
+        assert!(interpolate("${PORT", lookup).is_err());
+        assert!(interpolate("${}", lookup).is_err());
+        assert!(interpolate("${1PORT}", lookup).is_err());
+        assert!(interpolate("${PO RT}", lookup).is_err());
+        assert!(interpolate("${PORT:=80}", lookup).is_err());
+    }
+}
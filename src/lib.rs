@@ -1,37 +1,160 @@
 use env_reader::env_var_exists;
 use log::*;
+use std::env;
 use std::option::Option;
-use std::path::Path;
-use std::time::Duration;
 
+pub mod backoff;
+pub mod barrier;
+pub mod cli;
+pub mod command;
+pub mod compose;
+pub mod config_file;
+pub mod docker;
 pub mod env_reader;
+pub mod exit_code;
+pub mod explain;
+pub mod hooks;
+pub mod interpolation;
+pub mod kubernetes;
+pub mod logger;
+pub mod marker;
+pub mod process;
+pub mod signal;
 pub mod sleeper;
+pub mod status;
+pub mod target;
+pub mod watch;
 
+#[derive(Clone)]
 pub struct Command {
     pub program: String,
     pub argv: Vec<String>,
 }
 
+#[derive(Clone, Default)]
+pub struct Stage {
+    pub name: String,
+    pub hosts: String,
+    pub paths: String,
+    pub processes: String,
+    pub check_commands: String,
+    pub timeout: Option<u64>,
+    pub wait_before: u64,
+    pub wait_after: u64,
+}
+
+impl Stage {
+    // The timeout of a stage is bounded by the global one
+    pub fn timeout_to_string(&self, global_timeout: u64) -> String {
+        match self.timeout {
+            Some(timeout) => format!("{} seconds", timeout.min(global_timeout)),
+            None => "global".to_string(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Config {
     pub hosts: String,
     pub paths: String,
+    pub processes: String,
+    pub check_commands: String,
+    pub stages: Vec<Stage>,
     pub command: Option<(Command, String)>,
     pub global_timeout: u64,
     pub tcp_connection_timeout: u64,
     pub wait_before: u64,
     pub wait_after: u64,
     pub wait_sleep_interval: u64,
+    pub backoff: backoff::Backoff,
+    pub success_threshold: u64,
+    pub check_command_timeout: u64,
+    pub check_command_exit_code: i32,
+    pub docker_socket: String,
+    pub kubernetes: kubernetes::Cluster,
+    pub explain: Option<explain::Format>,
+    pub watch: Option<watch::Watch>,
+    pub status_addr: Option<String>,
+    pub barrier_addr: Option<String>,
+    pub timeout_exit_code: i32,
+    pub hooks: hooks::Hooks,
+    pub marker: Option<marker::Marker>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            hosts: "".to_string(),
+            paths: "".to_string(),
+            processes: "".to_string(),
+            check_commands: "".to_string(),
+            stages: vec![],
+            command: None,
+            global_timeout: 30,
+            tcp_connection_timeout: 5,
+            wait_before: 0,
+            wait_after: 0,
+            wait_sleep_interval: 1,
+            backoff: backoff::Backoff::default(),
+            success_threshold: 1,
+            check_command_timeout: 5,
+            check_command_exit_code: 0,
+            docker_socket: docker::DEFAULT_SOCKET.to_string(),
+            kubernetes: kubernetes::Cluster::default(),
+            explain: None,
+            watch: None,
+            status_addr: None,
+            barrier_addr: None,
+            timeout_exit_code: exit_code::TIMEOUT,
+            hooks: hooks::Hooks::default(),
+            marker: None,
+        }
+    }
 }
 
+const STAGE_PREFIX: &str = "WAIT_STAGE_";
 const LINE_SEPARATOR: &str = "--------------------------------------------------------";
 
-pub fn wait(sleep: &mut dyn sleeper::Sleeper, config: &Config, on_timeout: &mut dyn FnMut()) {
-    info!("{}", LINE_SEPARATOR);
-    info!(" docker-compose-wait {}", env!("CARGO_PKG_VERSION"));
-    info!("---------------------------");
+// Fails without waiting if the targets cannot be parsed or if the status or barrier port cannot be bound
+pub fn wait(
+    sleep: &mut dyn sleeper::Sleeper,
+    config: &Config,
+    on_timeout: &mut dyn FnMut(),
+) -> Result<(), String> {
+    if let Some(format) = config.explain {
+        println!("{}", explain::explain(config, format)?);
+        return Ok(());
+    }
+
+    separator();
+    info!(
+        event = "started", version = env!("CARGO_PKG_VERSION");
+        " docker-compose-wait {}", env!("CARGO_PKG_VERSION")
+    );
+    if !logger::is_json() {
+        info!("---------------------------");
+    }
     debug!("Starting with configuration:");
     debug!(" - Hosts to be waiting for: [{}]", config.hosts);
     debug!(" - Paths to be waiting for: [{}]", config.paths);
+    debug!(" - Processes to be waiting for: [{}]", config.processes);
+    debug!(
+        " - Check commands to be waiting for: [{}]",
+        config.check_commands
+    );
+    for stage in &config.stages {
+        debug!(
+            " - Stage [{}]: hosts [{}], paths [{}], processes [{}], check commands [{}], timeout {}, sleeping time before {} and after {} seconds",
+            stage.name,
+            stage.hosts,
+            stage.paths,
+            stage.processes,
+            stage.check_commands,
+            stage.timeout_to_string(config.global_timeout),
+            stage.wait_before,
+            stage.wait_after
+        );
+    }
     debug!(
         " - Timeout before failure: {} seconds ",
         config.global_timeout
@@ -40,96 +163,426 @@ pub fn wait(sleep: &mut dyn sleeper::Sleeper, config: &Config, on_timeout: &mut
         " - TCP connection timeout before retry: {} seconds ",
         config.tcp_connection_timeout
     );
+    debug!(
+        " - Check command timeout before retry: {} seconds ",
+        config.check_command_timeout
+    );
+    debug!(
+        " - Check command exit code on success: {}",
+        config.check_command_exit_code
+    );
 
     if let Some((_, command_string)) = &config.command {
         debug!(" - Command to run once ready: {}", command_string);
     }
+    if let Some(addr) = &config.status_addr {
+        debug!(" - Address of the status endpoint: {}", addr);
+    }
+    if let Some(addr) = &config.barrier_addr {
+        debug!(" - Address of the barrier once ready: {}", addr);
+    }
+    if let Some(marker) = &config.marker {
+        debug!(" - Marker file once ready: {}", marker.path);
+    }
+    if let Some(watch) = &config.watch {
+        debug!(
+            " - Watch of the targets once the command is started: {}",
+            watch
+        );
+    }
 
     debug!(
-        " - Sleeping time before checking for hosts/paths availability: {} seconds",
+        " - Sleeping time before checking for hosts/paths/processes availability: {} seconds",
         config.wait_before
     );
     debug!(
-        " - Sleeping time once all hosts/paths are available: {} seconds",
+        " - Sleeping time once all hosts/paths/processes are available: {} seconds",
         config.wait_after
     );
     debug!(
         " - Sleeping time between retries: {} seconds",
         config.wait_sleep_interval
     );
-    debug!("{}", LINE_SEPARATOR);
+    debug!(" - Backoff between retries: {}", config.backoff);
+    debug!(
+        " - Consecutive successful checks before availability: {}",
+        config.success_threshold
+    );
+    if !logger::is_json() {
+        debug!("{}", LINE_SEPARATOR);
+    }
+
+    // A marker left by a previous run must not let the siblings start too early
+    if let Some(marker) = &config.marker {
+        marker.remove();
+    }
+
+    // The targets of all the stages are known from the start, so that the status
+    // and the report on timeout also show the stages that are not started yet
+    let status = status::Status::default();
+    let targets = target::from_config(config)?;
+    let first = status.add(&targets, status::State::Waiting);
+    let stages = config
+        .stages
+        .iter()
+        .map(|stage| {
+            let targets = target::from_stage(stage, config)?;
+            Ok((status.add(&targets, status::State::NotStarted), targets))
+        })
+        .collect::<Result<Vec<(usize, Vec<target::TargetSpec>)>, String>>()?;
+    if let Some(addr) = &config.status_addr {
+        let addr = status::serve(addr, status.clone())
+            .map_err(|e| format!("failed to listen on [{}]: {}", addr, e))?;
+        info!("Serving the status on [http://{}/status]", addr);
+    }
 
     if config.wait_before > 0 {
         info!(
-            "Waiting {} seconds before checking for hosts/paths availability",
+            "Waiting {} seconds before checking for hosts/paths/processes availability",
             config.wait_before
         );
-        info!("{}", LINE_SEPARATOR);
+        separator();
         sleep.sleep(config.wait_before);
     }
 
     sleep.reset();
 
-    if !config.hosts.trim().is_empty() {
-        for host in config.hosts.trim().split(',') {
-            info!("Checking availability of host [{}]", host);
-            while !port_check::is_port_reachable_with_timeout(
-                host.trim(),
-                Duration::from_secs(config.tcp_connection_timeout),
-            ) {
-                info!("Host [{}] not yet available...", host);
-                if sleep.elapsed(config.global_timeout) {
-                    error!(
-                        "Timeout! After {} seconds some hosts are still not reachable",
-                        config.global_timeout
-                    );
-                    on_timeout();
-                    return;
-                }
-                sleep.sleep(config.wait_sleep_interval);
-            }
-            info!("Host [{}] is now available!", host);
-            info!("{}", LINE_SEPARATOR);
+    let mut skipped = match wait_for_targets(sleep, config, &status, &targets, first, 0) {
+        Ok(skipped) => skipped,
+        Err(event) => {
+            status.log_report();
+            config.hooks.run(&event, sleep.elapsed_units());
+            on_timeout();
+            return Ok(());
         }
-    }
+    };
 
-    if !config.paths.trim().is_empty() {
-        for path in config.paths.trim().split(',') {
-            info!("Checking availability of path [{}]", path);
-            while !Path::new(path.trim()).exists() {
-                info!("Path {} not yet available...", path);
-                if sleep.elapsed(config.global_timeout) {
-                    error!(
-                        "Timeout! After [{}] seconds some paths are still not reachable",
-                        config.global_timeout
-                    );
-                    on_timeout();
-                    return;
-                }
-                sleep.sleep(config.wait_sleep_interval);
+    for (stage, (first, targets)) in config.stages.iter().zip(&stages) {
+        info!(
+            event = "stage_started", stage = stage.name.as_str();
+            "Starting stage [{}]", stage.name
+        );
+        if stage.wait_before > 0 {
+            info!(
+                "Waiting {} seconds before checking for stage [{}] availability",
+                stage.wait_before, stage.name
+            );
+            sleep.sleep(stage.wait_before);
+        }
+        separator();
+
+        let started_at = sleep.elapsed_units();
+        match wait_for_targets(sleep, config, &status, targets, *first, started_at) {
+            Ok(stage_skipped) => skipped.extend(stage_skipped),
+            Err(event) => {
+                status.log_report();
+                config.hooks.run(&event, sleep.elapsed_units());
+                on_timeout();
+                return Ok(());
             }
-            info!("Path [{}] is now available!", path);
-            info!("{}", LINE_SEPARATOR);
+        };
+
+        if stage.wait_after > 0 {
+            info!(
+                "Waiting {} seconds after stage [{}] availability",
+                stage.wait_after, stage.name
+            );
+            separator();
+            sleep.sleep(stage.wait_after);
         }
     }
 
     if config.wait_after > 0 {
         info!(
-            "Waiting {} seconds after hosts/paths availability",
+            "Waiting {} seconds after hosts/paths/processes availability",
             config.wait_after
         );
-        info!("{}", LINE_SEPARATOR);
+        separator();
         sleep.sleep(config.wait_after);
     }
 
-    info!("docker-compose-wait - Everything's fine, the application can now start!");
-    info!("{}", LINE_SEPARATOR);
+    status.set_completed();
+    if skipped.is_empty() {
+        info!(
+            event = "ready";
+            "docker-compose-wait - Everything's fine, the application can now start!"
+        );
+    } else {
+        warn!(
+            event = "ready", skipped = skipped.join(", ").as_str();
+            "docker-compose-wait - The application can now start, but some optional targets are not available: [{}]",
+            skipped.join(", ")
+        );
+    }
+    separator();
+    let reason = if skipped.is_empty() {
+        "".to_string()
+    } else {
+        format!("optional targets not available: [{}]", skipped.join(", "))
+    };
+    config.hooks.run(
+        &hooks::Event::new(hooks::READY, &reason),
+        sleep.elapsed_units(),
+    );
+
+    let targets = target::from_config_and_stages(config)?;
+    if let Some(marker) = &config.marker {
+        let available: Vec<String> = targets
+            .iter()
+            .map(|spec| spec.to_string())
+            .filter(|spec| !skipped.contains(spec))
+            .collect();
+        if let Err(e) = marker.write(&available, &skipped) {
+            error!("Failed to write marker file [{}]: {}", marker.path, e);
+        }
+    }
+    if let Some(addr) = &config.barrier_addr {
+        let addr = barrier::open(addr, status.clone())
+            .map_err(|e| format!("failed to listen on [{}]: {}", addr, e))?;
+        info!(event = "barrier_open"; "Barrier open on [{}]", addr);
+        if config.command.is_some() && config.watch.is_none() {
+            warn!(
+                "The barrier is closed once the command is started, unless the watch mode is enabled"
+            );
+        }
+    }
 
     if let Some((command, _)) = &config.command {
+        if let Some(watch) = &config.watch {
+            signal::forward_signals();
+            let mut child = match std::process::Command::new(&command.program)
+                .args(&command.argv)
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(e) => {
+                    error!("Failed to start the command: {}", e);
+                    if let Some(marker) = &config.marker {
+                        marker.remove();
+                    }
+                    std::process::exit(exit_code::from_spawn_error(&e));
+                }
+            };
+            let code = watch::supervise(sleep, config, watch, &status, &targets, &mut child);
+            if let Some(marker) = &config.marker {
+                marker.remove();
+            }
+            std::process::exit(code);
+        }
         let err = exec::Command::new(&command.program)
             .args(&command.argv)
             .exec();
-        panic!("{}", err);
+        error!("Failed to run the command: {}", err);
+        std::process::exit(exit_code::from_exec_error(&err));
+    }
+    Ok(())
+}
+
+// Checks every target exactly once, without sleeping or retrying, e.g. for a Docker HEALTHCHECK.
+// Returns whether all the required targets are available, together with a compact status line.
+pub fn check(config: &Config) -> Result<(bool, String), String> {
+    let targets = target::from_config_and_stages(config)?;
+    let mut failed = vec![];
+    let mut skipped = vec![];
+    for spec in &targets {
+        if !spec.target.is_available(config) {
+            if spec.optional {
+                skipped.push(spec.to_string());
+            } else {
+                failed.push(spec.to_string());
+            }
+        }
+    }
+    let mut status = format!(
+        "{}: {}/{} targets available",
+        if failed.is_empty() {
+            "healthy"
+        } else {
+            "unhealthy"
+        },
+        targets.len() - failed.len() - skipped.len(),
+        targets.len()
+    );
+    if !failed.is_empty() {
+        status = format!("{}, not available: [{}]", status, failed.join(", "));
+    }
+    if !skipped.is_empty() {
+        status = format!(
+            "{}, optional not available: [{}]",
+            status,
+            skipped.join(", ")
+        );
+    }
+    Ok((failed.is_empty(), status))
+}
+
+struct TargetState<'a> {
+    spec: &'a target::TargetSpec,
+    schedule: backoff::Schedule,
+    successes: u64,
+    attempts: u64,
+    next_check_at: u64,
+    deadline: u64,
+    available: bool,
+    skipped: bool,
+}
+
+impl TargetState<'_> {
+    // The next check never happens after the deadline, whatever the interval,
+    // so that the timeout is noticed on time
+    fn schedule_next_check(&mut self, now: u64, interval: u64) {
+        self.next_check_at = now.saturating_add(interval);
+        if now < self.deadline {
+            self.next_check_at = self.next_check_at.min(self.deadline);
+        }
+    }
+}
+
+fn separator() {
+    if !logger::is_json() {
+        info!("{}", LINE_SEPARATOR);
+    }
+}
+
+// All the targets are checked in rounds, each one following its own retry schedule,
+// until they are all available or one of them times out.
+// The timeouts of the targets start at `started_at`, while the global timeout bounds the whole run.
+// Returns the optional targets skipped because of a timeout, or the timeout of a required target.
+fn wait_for_targets(
+    sleep: &mut dyn sleeper::Sleeper,
+    config: &Config,
+    status: &status::Status,
+    targets: &[target::TargetSpec],
+    first: usize,
+    started_at: u64,
+) -> Result<Vec<String>, hooks::Event> {
+    let mut states: Vec<TargetState> = targets
+        .iter()
+        .enumerate()
+        .map(|(index, spec)| {
+            status.set_state(first + index, status::State::Waiting);
+            info!(
+                event = "checking", target = spec.target.name(), kind = spec.target.kind();
+                "Checking availability of {}", spec
+            );
+            TargetState {
+                spec,
+                schedule: config.backoff.schedule(spec.interval),
+                successes: 0,
+                attempts: 0,
+                next_check_at: 0,
+                deadline: started_at
+                    .saturating_add(spec.timeout)
+                    .min(config.global_timeout),
+                available: false,
+                skipped: false,
+            }
+        })
+        .collect();
+
+    loop {
+        // Once a target timed out, the remaining ones are checked anyway
+        // so that the report shows their latest state.
+        let mut timed_out = None;
+        for (index, state) in states
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, state)| !state.available && !state.skipped)
+        {
+            if timed_out.is_none() && sleep.elapsed_units() < state.next_check_at {
+                continue;
+            }
+            let result = state.spec.target.check(config);
+            let available = result.is_ok() && state.successes + 1 >= state.spec.success_threshold;
+            status.checked(first + index, &result, available);
+            state.attempts += 1;
+            let (target, kind, attempt) = (
+                state.spec.target.name(),
+                state.spec.target.kind(),
+                state.attempts,
+            );
+            if result.is_ok() {
+                state.successes += 1;
+                if state.successes >= state.spec.success_threshold {
+                    state.available = true;
+                    info!(
+                        event = "available", target, kind, attempt;
+                        "{} is now available!", state.spec
+                    );
+                    separator();
+                    config.hooks.run(
+                        &hooks::Event::for_target(hooks::AVAILABLE, state.spec, ""),
+                        sleep.elapsed_units(),
+                    );
+                    continue;
+                }
+                info!(
+                    event = "check_succeeded", target, kind, attempt;
+                    "{} available ({}/{} consecutive successful checks)...",
+                    state.spec, state.successes, state.spec.success_threshold
+                );
+                state.schedule_next_check(sleep.elapsed_units(), state.spec.interval);
+            } else {
+                state.successes = 0;
+                let error = result.err().unwrap_or_default();
+                info!(
+                    event = "not_available", target, kind, attempt, error = error.as_str();
+                    "{} not yet available...", state.spec
+                );
+                if sleep.elapsed(state.deadline) {
+                    if state.spec.optional {
+                        warn!(
+                            event = "skipped", target, kind, attempt, error = error.as_str();
+                            "After {} seconds optional {} is still not available. Skipping it.",
+                            state.spec.timeout, state.spec
+                        );
+                        separator();
+                        state.skipped = true;
+                        status.set_state(first + index, status::State::Skipped);
+                        config.hooks.run(
+                            &hooks::Event::for_target(hooks::SKIPPED, state.spec, &error),
+                            sleep.elapsed_units(),
+                        );
+                        continue;
+                    }
+                    error!(
+                        event = "timeout", target, kind, attempt, error = error.as_str();
+                        "Timeout! After {} seconds {} is still not available",
+                        state.spec.timeout, state.spec
+                    );
+                    status.set_state(first + index, status::State::TimedOut);
+                    config.hooks.run(
+                        &hooks::Event::for_target(hooks::TIMED_OUT, state.spec, &error),
+                        sleep.elapsed_units(),
+                    );
+                    timed_out.get_or_insert_with(|| {
+                        hooks::Event::for_target(hooks::TIMEOUT, state.spec, &error)
+                    });
+                    continue;
+                }
+                let interval = state.schedule.next_interval();
+                state.schedule_next_check(sleep.elapsed_units(), interval);
+            }
+        }
+        if let Some(event) = timed_out {
+            return Err(event);
+        }
+
+        match states
+            .iter()
+            .filter(|state| !state.available && !state.skipped)
+            .map(|state| state.next_check_at)
+            .min()
+        {
+            Some(next_check_at) => sleep.sleep(next_check_at.saturating_sub(sleep.elapsed_units())),
+            None => {
+                return Ok(states
+                    .iter()
+                    .filter(|state| state.skipped)
+                    .map(|state| state.spec.to_string())
+                    .collect());
+            }
+        }
     }
 }
 
@@ -151,37 +604,291 @@ pub fn parse_command<S: Into<String>>(
     )))
 }
 
-pub fn config_from_env() -> Config {
-    Config {
-        hosts: env_reader::env_var("WAIT_HOSTS", "".to_string()),
-        paths: env_reader::env_var("WAIT_PATHS", "".to_string()),
-        command: parse_command(env_reader::env_var("WAIT_COMMAND", "".to_string()))
-            .expect("failed to parse command value from environment"),
-        global_timeout: to_int(&legacy_or_new("WAIT_HOSTS_TIMEOUT", "WAIT_TIMEOUT", ""), 30),
+// Every invalid value is reported as an error, before anything is waited for
+pub fn config_from_env() -> Result<Config, String> {
+    set_env_prefix()?;
+    load_config_file()?;
+    let config = Config {
+        hosts: with_compose_hosts(interpolated_env_var("WAIT_HOSTS")?)?,
+        paths: interpolated_env_var("WAIT_PATHS")?,
+        processes: env_reader::try_env_var("WAIT_PROCESSES", "".to_string())?,
+        check_commands: env_reader::try_env_var("WAIT_CHECK_COMMANDS", "".to_string())?,
+        stages: stages_from_env()?,
+        command: parse_command(interpolated_env_var("WAIT_COMMAND")?)
+            .map_err(|e| format!("failed to parse the value of [WAIT_COMMAND]: {}", e))?,
+        global_timeout: to_int(
+            &legacy_or_new("WAIT_HOSTS_TIMEOUT", "WAIT_TIMEOUT", "")?,
+            30,
+        ),
         tcp_connection_timeout: to_int(
-            &env_reader::env_var("WAIT_HOST_CONNECT_TIMEOUT", "".to_string()),
+            &env_reader::try_env_var("WAIT_HOST_CONNECT_TIMEOUT", "".to_string())?,
             5,
         ),
-        wait_before: to_int(&legacy_or_new("WAIT_BEFORE_HOSTS", "WAIT_BEFORE", ""), 0),
-        wait_after: to_int(&legacy_or_new("WAIT_AFTER_HOSTS", "WAIT_AFTER", ""), 0),
+        wait_before: to_int(&legacy_or_new("WAIT_BEFORE_HOSTS", "WAIT_BEFORE", "")?, 0),
+        wait_after: to_int(&legacy_or_new("WAIT_AFTER_HOSTS", "WAIT_AFTER", "")?, 0),
         wait_sleep_interval: to_int(
-            &env_reader::env_var("WAIT_SLEEP_INTERVAL", "".to_string()),
+            &env_reader::try_env_var("WAIT_SLEEP_INTERVAL", "".to_string())?,
             1,
         ),
+        backoff: backoff::Backoff {
+            strategy: backoff::strategy_from_str(
+                &env_reader::try_env_var("WAIT_BACKOFF", "".to_string())?,
+                to_float(
+                    &env_reader::try_env_var("WAIT_BACKOFF_MULTIPLIER", "".to_string())?,
+                    2.0,
+                ),
+                to_int(
+                    &env_reader::try_env_var("WAIT_BACKOFF_MAX_INTERVAL", "".to_string())?,
+                    30,
+                ),
+            ),
+            jitter: backoff::jitter_from_str(&env_reader::try_env_var(
+                "WAIT_BACKOFF_JITTER",
+                "".to_string(),
+            )?),
+        },
+        success_threshold: to_int(
+            &env_reader::try_env_var("WAIT_SUCCESS_THRESHOLD", "".to_string())?,
+            1,
+        )
+        .max(1),
+        check_command_timeout: to_int(
+            &env_reader::try_env_var("WAIT_CHECK_COMMAND_TIMEOUT", "".to_string())?,
+            5,
+        ),
+        check_command_exit_code: to_int(
+            &env_reader::try_env_var("WAIT_CHECK_COMMAND_EXIT_CODE", "".to_string())?,
+            0,
+        ) as i32,
+        docker_socket: env_reader::try_env_var(
+            "WAIT_DOCKER_SOCKET",
+            docker::DEFAULT_SOCKET.to_string(),
+        )?,
+        kubernetes: kubernetes::Cluster {
+            api_server: env_reader::try_env_var(
+                "WAIT_K8S_API_SERVER",
+                kubernetes::in_cluster_api_server(),
+            )?,
+            token_file: env_reader::try_env_var(
+                "WAIT_K8S_TOKEN_FILE",
+                kubernetes::TOKEN_FILE.to_string(),
+            )?,
+            ca_file: env_reader::try_env_var("WAIT_K8S_CA_FILE", kubernetes::CA_FILE.to_string())?,
+        },
+        explain: explain::format_from_str(&env_reader::try_env_var(
+            "WAIT_DRY_RUN",
+            "".to_string(),
+        )?),
+        watch: watch_from_env()?,
+        status_addr: Some(env_reader::try_env_var("WAIT_STATUS_ADDR", "".to_string())?)
+            .map(|addr| addr.trim().to_string())
+            .filter(|addr| !addr.is_empty()),
+        barrier_addr: Some(env_reader::try_env_var(
+            "WAIT_BARRIER_ADDR",
+            "".to_string(),
+        )?)
+        .map(|addr| barrier::to_addr(&addr))
+        .filter(|addr| !addr.is_empty()),
+        timeout_exit_code: timeout_exit_code_from_env()?,
+        hooks: hooks::Hooks {
+            on_timeout: hook_from_env("WAIT_ON_TIMEOUT_COMMAND")?,
+            on_ready: hook_from_env("WAIT_ON_READY_COMMAND")?,
+            on_target_change: hook_from_env("WAIT_ON_TARGET_CHANGE_COMMAND")?,
+            timeout: to_int(
+                &env_reader::try_env_var("WAIT_HOOK_TIMEOUT", "".to_string())?,
+                30,
+            ),
+        },
+        marker: marker_from_env()?,
+    };
+    target::from_config_and_stages(&config)
+        .map_err(|e| format!("failed to parse the targets: {}", e))?;
+    Ok(config)
+}
+
+// Images without a shell cannot expand variables in the values, so the common cases are handled here
+fn interpolated_env_var(var_name: &str) -> Result<String, String> {
+    interpolation::interpolate(
+        &env_reader::try_env_var(var_name, "".to_string())?,
+        |name| env::var(name).ok(),
+    )
+    .map_err(|e| format!("failed to interpolate [{}]: {}", var_name, e))
+}
+
+// Adds the hosts the service depends on in the docker-compose file, if any
+fn with_compose_hosts(hosts: String) -> Result<String, String> {
+    let path = env_reader::try_env_var("WAIT_COMPOSE_FILE", "".to_string())?;
+    if path.trim().is_empty() {
+        return Ok(hosts);
     }
+    let service = env_reader::try_env_var("WAIT_COMPOSE_SERVICE", "".to_string())?;
+    if service.trim().is_empty() {
+        return Err(
+            "WAIT_COMPOSE_SERVICE is required to derive the hosts from a compose file".to_string(),
+        );
+    }
+    let compose_hosts = compose::targets(path.trim(), service.trim())
+        .map_err(|e| format!("failed to derive the hosts from the compose file: {}", e))?
+        .join(", ");
+    debug!(
+        "Hosts derived from compose file [{}] for service [{}]: [{}]",
+        path.trim(),
+        service.trim(),
+        compose_hosts
+    );
+    Ok(match (hosts.trim().is_empty(), compose_hosts.is_empty()) {
+        (_, true) => hosts,
+        (true, false) => compose_hosts,
+        (false, false) => format!("{}, {}", hosts, compose_hosts),
+    })
+}
+
+// A custom prefix and the profiles allow a single image to run distinct wait configurations,
+// e.g. with `WAIT_PROFILE=migrate` the hosts are read from `WAIT_MIGRATE_HOSTS`
+fn set_env_prefix() -> Result<(), String> {
+    env_reader::set_prefix(env_reader::DEFAULT_PREFIX);
+    let prefix = env_reader::try_env_var("WAIT_ENV_PREFIX", "".to_string())?;
+    let profile = env_reader::try_env_var("WAIT_PROFILE", "".to_string())?;
+    let mut prefix = match prefix.trim() {
+        "" => env_reader::DEFAULT_PREFIX.to_string(),
+        prefix => prefix.to_string(),
+    };
+    if !profile.trim().is_empty() {
+        prefix = format!(
+            "{}{}_",
+            prefix,
+            profile.trim().replace('-', "_").to_uppercase()
+        );
+    }
+    if prefix != env_reader::DEFAULT_PREFIX {
+        info!(
+            "Reading the configuration from the variables with prefix [{}]",
+            prefix
+        );
+    }
+    env_reader::set_prefix(&prefix);
+    Ok(())
+}
+
+// Values from the configuration file are overridden by environment variables
+fn load_config_file() -> Result<(), String> {
+    env_reader::set_file_vars(vec![]);
+    let path = env_reader::try_env_var("WAIT_CONFIG", "".to_string())?;
+    if !path.trim().is_empty() {
+        env_reader::set_file_vars(
+            config_file::load(path.trim())
+                .map_err(|e| format!("failed to load the configuration file: {}", e))?,
+        );
+    }
+    Ok(())
+}
+
+// Stages are declared with variables in the form WAIT_STAGE_<number>_<option>
+// and are executed in ascending order of their numbers
+fn stages_from_env() -> Result<Vec<Stage>, String> {
+    let mut numbers: Vec<u64> = env_reader::env_var_names()
+        .iter()
+        .filter_map(|name| name.strip_prefix(STAGE_PREFIX))
+        .filter_map(|name| name.split_once('_'))
+        .filter_map(|(number, _)| number.parse::<u64>().ok())
+        .collect();
+    numbers.sort();
+    numbers.dedup();
+    numbers
+        .into_iter()
+        .map(|number| {
+            let var_name = |option: &str| format!("{}{}_{}", STAGE_PREFIX, number, option);
+            let var = |option: &str| env_reader::try_env_var(&var_name(option), "".to_string());
+            Ok(Stage {
+                name: match var("NAME")? {
+                    name if name.trim().is_empty() => number.to_string(),
+                    name => name.trim().to_string(),
+                },
+                hosts: interpolated_env_var(&var_name("HOSTS"))?,
+                paths: interpolated_env_var(&var_name("PATHS"))?,
+                processes: var("PROCESSES")?,
+                check_commands: var("CHECK_COMMANDS")?,
+                timeout: var("TIMEOUT")?.parse::<u64>().ok(),
+                wait_before: to_int(&var("BEFORE")?, 0),
+                wait_after: to_int(&var("AFTER")?, 0),
+            })
+        })
+        .collect()
 }
 
-fn legacy_or_new(legacy_var_name: &str, var_name: &str, default: &str) -> String {
+fn marker_from_env() -> Result<Option<marker::Marker>, String> {
+    let path = env_reader::try_env_var("WAIT_MARKER_FILE", "".to_string())?;
+    if path.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(marker::Marker {
+        path: path.trim().to_string(),
+        content: env_reader::try_env_var(
+            "WAIT_MARKER_CONTENT",
+            marker::DEFAULT_CONTENT.to_string(),
+        )?,
+    }))
+}
+
+fn hook_from_env(var_name: &str) -> Result<Option<(Command, String)>, String> {
+    parse_command(env_reader::try_env_var(var_name, "".to_string())?)
+        .map_err(|e| format!("failed to parse the value of [{}]: {}", var_name, e))
+}
+
+// The exit code is truncated to its lowest 8 bits by the system, so larger values are rejected
+fn timeout_exit_code_from_env() -> Result<i32, String> {
+    let value = env_reader::try_env_var("WAIT_TIMEOUT_EXIT_CODE", "".to_string())?;
+    match to_int(&value, exit_code::TIMEOUT as u64) {
+        code @ 1..=255 => Ok(code as i32),
+        _ => Err(format!(
+            "invalid value of WAIT_TIMEOUT_EXIT_CODE [{}], it must be between 1 and 255",
+            value.trim()
+        )),
+    }
+}
+
+fn watch_from_env() -> Result<Option<watch::Watch>, String> {
+    let signal = env_reader::try_env_var("WAIT_WATCH_SIGNAL", "".to_string())?;
+    let action = watch::action_from_str(
+        &env_reader::try_env_var("WAIT_WATCH", "".to_string())?,
+        match signal.trim() {
+            "" => libc::SIGHUP,
+            signal => signal::signal_from_str(signal)
+                .ok_or_else(|| format!("invalid value of WAIT_WATCH_SIGNAL [{}]", signal))?,
+        },
+    );
+    let Some(action) = action else {
+        return Ok(None);
+    };
+    Ok(Some(watch::Watch {
+        action,
+        interval: to_int(
+            &env_reader::try_env_var("WAIT_WATCH_INTERVAL", "".to_string())?,
+            10,
+        )
+        .max(1),
+        failure_threshold: to_int(
+            &env_reader::try_env_var("WAIT_WATCH_FAILURE_THRESHOLD", "".to_string())?,
+            3,
+        )
+        .max(1),
+        kill_timeout: to_int(
+            &env_reader::try_env_var("WAIT_WATCH_KILL_TIMEOUT", "".to_string())?,
+            10,
+        ),
+    }))
+}
+
+fn legacy_or_new(legacy_var_name: &str, var_name: &str, default: &str) -> Result<String, String> {
     let mut temp_value = default.to_string();
     if env_var_exists(legacy_var_name) {
         warn!(
             "Environment variable [{}] is deprecated. Use [{}] instead.",
             legacy_var_name, var_name
         );
-        temp_value = env_reader::env_var(legacy_var_name, temp_value);
+        temp_value = env_reader::try_env_var(legacy_var_name, temp_value)?;
     }
-    temp_value = env_reader::env_var(var_name, temp_value);
-    temp_value
+    env_reader::try_env_var(var_name, temp_value)
 }
 
 fn to_int(number: &str, default: u64) -> u64 {
@@ -191,6 +898,13 @@ fn to_int(number: &str, default: u64) -> u64 {
     }
 }
 
+fn to_float(number: &str, default: f64) -> f64 {
+    match number.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 1.0 => value,
+        _ => default,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -198,8 +912,54 @@ mod test {
     use std::env;
     use std::sync::Mutex;
 
+    // Shared with the env_reader tests, which also change the environment and the file variables
     lazy_static! {
-        static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
+        pub(crate) static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
+    }
+
+    #[test]
+    fn should_report_a_target_available_only_after_its_success_threshold() {
+        let status = status::Status::default();
+        let waiting = {
+            let status = status.clone();
+            std::thread::spawn(move || {
+                let config = Config {
+                    paths: "./target?success_threshold=3".to_string(),
+                    wait_sleep_interval: 50,
+                    global_timeout: 5000,
+                    ..Default::default()
+                };
+                let targets = target::from_config(&config).unwrap();
+                let first = status.add(&targets, status::State::NotStarted);
+                let mut sleeper = sleeper::MillisSleeper::default();
+                wait_for_targets(&mut sleeper, &config, &status, &targets, first, 0)
+            })
+        };
+        let mut waiting_attempts = vec![];
+        while !waiting.is_finished() {
+            let json = status.to_json();
+            let target = &json["targets"][0];
+            match target["state"].as_str() {
+                Some("available") => assert_eq!(3, target["attempts"]),
+                Some(_) => waiting_attempts.push(target["attempts"].as_u64().unwrap()),
+                None => {}
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(waiting.join().unwrap().is_ok());
+        assert_eq!("available", status.to_json()["targets"][0]["state"]);
+        assert!(waiting_attempts.contains(&2));
+    }
+
+    #[test]
+    fn should_describe_the_timeout_of_a_stage() {
+        let stage = Stage {
+            timeout: Some(50),
+            ..Default::default()
+        };
+        assert_eq!("50 seconds", stage.timeout_to_string(60));
+        assert_eq!("30 seconds", stage.timeout_to_string(30));
+        assert_eq!("global", Stage::default().timeout_to_string(30));
     }
 
     #[test]
@@ -226,23 +986,39 @@ mod test {
         assert_eq!(11, value)
     }
 
+    #[test]
+    fn should_return_float_value() {
+        assert_eq!(1.5, to_float("1.5", 2.0));
+        assert_eq!(3.0, to_float("3", 2.0));
+    }
+
+    #[test]
+    fn should_return_default_float_when_invalid_value() {
+        assert_eq!(2.0, to_float("0.5", 2.0));
+        assert_eq!(2.0, to_float("inf", 2.0));
+        assert_eq!(2.0, to_float("hello", 2.0));
+        assert_eq!(2.0, to_float("", 2.0));
+    }
+
     #[test]
     fn config_should_use_default_values() {
         let _guard = TEST_MUTEX.lock().unwrap();
         set_env("", "", "10o", "10", "", "abc", "");
-        let config = config_from_env();
+        let config = config_from_env().unwrap();
         assert_eq!("".to_string(), config.hosts);
         assert_eq!(30, config.global_timeout);
         assert_eq!(5, config.tcp_connection_timeout);
         assert_eq!(0, config.wait_before);
         assert_eq!(10, config.wait_after);
+        assert_eq!(exit_code::TIMEOUT, config.timeout_exit_code);
+        assert_eq!(docker::DEFAULT_SOCKET, config.docker_socket);
     }
 
     #[test]
     fn should_get_config_values_from_env() {
         let _guard = TEST_MUTEX.lock().unwrap();
         set_env("localhost:1234", "20", "2", "3", "4", "23", "");
-        let config = config_from_env();
+        let config = config_from_env().unwrap();
         assert_eq!("localhost:1234".to_string(), config.hosts);
         assert_eq!(20, config.global_timeout);
         assert_eq!(23, config.tcp_connection_timeout);
@@ -255,7 +1031,7 @@ mod test {
     fn should_get_default_config_values() {
         let _guard = TEST_MUTEX.lock().unwrap();
         set_env("localhost:1234", "", "", "", "", "", "");
-        let config = config_from_env();
+        let config = config_from_env().unwrap();
         assert_eq!("localhost:1234".to_string(), config.hosts);
         assert_eq!(30, config.global_timeout);
         assert_eq!(5, config.tcp_connection_timeout);
@@ -265,11 +1041,340 @@ mod test {
     }
 
     #[test]
-    #[should_panic]
-    fn should_panic_when_given_an_invalid_command() {
+    fn should_get_backoff_config_values_from_env() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        set_backoff_env("exponential", "1.5", "20", "equal");
+        let config = config_from_env().unwrap();
+        set_backoff_env("", "", "", "");
+        assert_eq!(
+            backoff::Backoff {
+                strategy: backoff::Strategy::Exponential {
+                    multiplier: 1.5,
+                    max_interval: 20
+                },
+                jitter: backoff::Jitter::Equal,
+            },
+            config.backoff
+        );
+    }
+
+    #[test]
+    fn should_get_default_backoff_config_values() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        set_backoff_env("exponential", "", "", "");
+        let config = config_from_env().unwrap();
+        set_backoff_env("", "", "", "");
+        assert_eq!(
+            backoff::Strategy::Exponential {
+                multiplier: 2.0,
+                max_interval: 30
+            },
+            config.backoff.strategy
+        );
+        assert_eq!(backoff::Jitter::None, config.backoff.jitter);
+    }
+
+    fn set_backoff_env(strategy: &str, multiplier: &str, max_interval: &str, jitter: &str) {
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_BACKOFF", strategy) };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_BACKOFF_MULTIPLIER", multiplier) };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_BACKOFF_MAX_INTERVAL", max_interval) };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_BACKOFF_JITTER", jitter) };
+    }
+
+    #[test]
+    fn should_get_stages_from_env() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        let vars = [
+            (
+                "WAIT_STAGE_10_PATHS",
+                "${STAGE_TEST_MARKER_DIR:-/tmp}/marker",
+            ),
+            ("WAIT_STAGE_2_NAME", " database "),
+            ("WAIT_STAGE_2_HOSTS", "db:5432"),
+            ("WAIT_STAGE_2_TIMEOUT", "60"),
+            ("WAIT_STAGE_2_BEFORE", "3"),
+            ("WAIT_STAGE_2_AFTER", "4"),
+            ("WAIT_STAGE_1_HOSTS", "${STAGE_TEST_VAULT_HOST}:8200"),
+            ("STAGE_TEST_VAULT_HOST", "vault"),
+            ("WAIT_STAGE_1_CHECK_COMMANDS", "vault status"),
+            ("WAIT_STAGE_1_PROCESSES", "nginx"),
+        ];
+        for (key, value) in vars {
+            // TODO: Audit that the environment access only happens in single-threaded code.
+            unsafe { env::set_var(key, value) };
+        }
+        let config = config_from_env().unwrap();
+        for (key, _) in vars {
+            // TODO: Audit that the environment access only happens in single-threaded code.
+            unsafe { env::remove_var(key) };
+        }
+
+        assert_eq!(3, config.stages.len());
+        assert_eq!("1", config.stages[0].name);
+        assert_eq!("vault:8200", config.stages[0].hosts);
+        assert_eq!("nginx", config.stages[0].processes);
+        assert_eq!("vault status", config.stages[0].check_commands);
+        assert_eq!(None, config.stages[0].timeout);
+        assert_eq!("database", config.stages[1].name);
+        assert_eq!("db:5432", config.stages[1].hosts);
+        assert_eq!(Some(60), config.stages[1].timeout);
+        assert_eq!(3, config.stages[1].wait_before);
+        assert_eq!(4, config.stages[1].wait_after);
+        assert_eq!("10", config.stages[2].name);
+        assert_eq!("/tmp/marker", config.stages[2].paths);
+        assert_eq!(0, config.stages[2].wait_before);
+    }
+
+    #[test]
+    fn should_get_config_values_from_file() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        let path = format!("./target/{}.toml", rand::random::<u64>());
+        std::fs::write(
+            &path,
+            r#"
+hosts = ["db:5432", { target = "cache:6379", optional = true }]
+timeout = 50
+before = 7
+sleep_interval = 3
+command = ["ls", "-al"]
+
+[[stages]]
+name = "vault"
+hosts = "vault:8200"
+"#,
+        )
+        .unwrap();
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_CONFIG", &path) };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("WAIT_BEFORE_HOSTS") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("WAIT_HOSTS_TIMEOUT") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("WAIT_HOSTS") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("WAIT_COMMAND") };
+        let config = config_from_env().unwrap();
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("WAIT_CONFIG") };
+
+        assert_eq!("db:5432, cache:6379?optional=true", config.hosts);
+        assert_eq!(50, config.global_timeout);
+        assert_eq!(7, config.wait_before);
+        // The environment variable takes precedence
+        assert_eq!(1, config.wait_sleep_interval);
+        assert_eq!("ls -al", config.command.unwrap().1);
+        assert_eq!(1, config.stages.len());
+        assert_eq!("vault", config.stages[0].name);
+        assert_eq!("vault:8200", config.stages[0].hosts);
+
+        let config = config_from_env().unwrap();
+        assert_eq!("", config.hosts);
+        assert!(config.stages.is_empty());
+    }
+
+    #[test]
+    fn should_interpolate_variables_in_the_command_and_targets() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env(
+            "${WAIT_TEST_HOST}:${WAIT_TEST_PORT:-5432}",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "/app --port ${WAIT_TEST_PORT:-80} --price $$5",
+        );
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_TEST_HOST", "db") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_PATHS", "/data/${WAIT_TEST_HOST}") };
+        let config = config_from_env().unwrap();
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("WAIT_TEST_HOST") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("WAIT_PATHS") };
+
+        assert_eq!("db:5432", config.hosts);
+        assert_eq!("/data/db", config.paths);
+        let (command, command_string) = config.command.unwrap();
+        assert_eq!("/app --port 80 --price $5", command_string);
+        assert_eq!(vec!["--port", "80", "--price", "$5"], command.argv);
+    }
+
+    #[test]
+    fn should_get_config_values_with_the_env_prefix_and_profile() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("host:1", "10", "", "", "", "", "");
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_PROFILE", "migrate") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_MIGRATE_HOSTS", "db:5432") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_MIGRATE_STAGE_1_PATHS", "/tmp/done") };
+        let config = config_from_env().unwrap();
+
+        assert_eq!("db:5432", config.hosts);
+        // There is no fallback to the variables without the profile
+        assert_eq!(30, config.global_timeout);
+        assert_eq!(1, config.stages.len());
+        assert_eq!("/tmp/done", config.stages[0].paths);
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("WAIT_ENV_PREFIX", "OTHER_") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("OTHER_MIGRATE_TIMEOUT", "20") };
+        let config = config_from_env().unwrap();
+
+        assert_eq!("", config.hosts);
+        assert_eq!(20, config.global_timeout);
+
+        for key in [
+            "WAIT_PROFILE",
+            "WAIT_ENV_PREFIX",
+            "WAIT_MIGRATE_HOSTS",
+            "WAIT_MIGRATE_STAGE_1_PATHS",
+            "OTHER_MIGRATE_TIMEOUT",
+        ] {
+            // TODO: Audit that the environment access only happens in single-threaded code.
+            unsafe { env::remove_var(key) };
+        }
+        let config = config_from_env().unwrap();
+        assert_eq!("host:1", config.hosts);
+        assert_eq!(10, config.global_timeout);
+    }
+
+    #[test]
+    fn should_get_hosts_from_the_compose_file() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("cache:6379", "", "", "", "", "", "");
+        let path = format!("./target/{}.yml", rand::random::<u64>());
+        std::fs::write(
+            &path,
+            r#"
+services:
+  app:
+    depends_on: [db]
+  db:
+    expose: [5432]
+"#,
+        )
+        .unwrap();
+        env_reader::set_override("WAIT_COMPOSE_FILE", path);
+        env_reader::set_override("WAIT_COMPOSE_SERVICE", "app".to_string());
+        let config = config_from_env().unwrap();
+        env_reader::set_override("WAIT_COMPOSE_FILE", "".to_string());
+
+        assert_eq!("cache:6379, db:5432", config.hosts);
+    }
+
+    #[test]
+    fn should_get_the_timeout_exit_code_from_env() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        env_reader::set_override("WAIT_TIMEOUT_EXIT_CODE", "75".to_string());
+        let config = config_from_env().unwrap();
+        env_reader::set_override("WAIT_TIMEOUT_EXIT_CODE", "".to_string());
+        assert_eq!(75, config.timeout_exit_code);
+    }
+
+    #[test]
+    fn should_reject_a_timeout_exit_code_out_of_range() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        for code in ["0", "256", "300"] {
+            env_reader::set_override("WAIT_TIMEOUT_EXIT_CODE", code.to_string());
+            let result = config_from_env();
+            env_reader::set_override("WAIT_TIMEOUT_EXIT_CODE", "".to_string());
+            assert_eq!(
+                Err(format!(
+                    "invalid value of WAIT_TIMEOUT_EXIT_CODE [{}], it must be between 1 and 255",
+                    code
+                )),
+                result.map(|config| config.timeout_exit_code)
+            );
+        }
+    }
+
+    #[test]
+    fn should_get_the_barrier_addr_from_env() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        assert_eq!(None, config_from_env().unwrap().barrier_addr);
+
+        env_reader::set_override("WAIT_BARRIER_ADDR", "7000".to_string());
+        let config = config_from_env().unwrap();
+        env_reader::set_override("WAIT_BARRIER_ADDR", "".to_string());
+        assert_eq!(Some("0.0.0.0:7000".to_string()), config.barrier_addr);
+    }
+
+    #[test]
+    fn should_get_the_marker_from_env() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        assert!(config_from_env().unwrap().marker.is_none());
+
+        env_reader::set_override("WAIT_MARKER_FILE", " /shared/deps.ready ".to_string());
+        let config = config_from_env().unwrap();
+        env_reader::set_override("WAIT_MARKER_FILE", "".to_string());
+        let marker = config.marker.unwrap();
+        assert_eq!("/shared/deps.ready", marker.path);
+        assert_eq!(marker::DEFAULT_CONTENT, marker.content);
+    }
+
+    #[test]
+    fn should_get_watch_config_values_from_env() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("", "", "", "", "", "", "");
+        assert_eq!(None, config_from_env().unwrap().watch);
+
+        env_reader::set_override("WAIT_WATCH", "signal".to_string());
+        env_reader::set_override("WAIT_WATCH_SIGNAL", "SIGUSR1".to_string());
+        env_reader::set_override("WAIT_WATCH_INTERVAL", "5".to_string());
+        let config = config_from_env().unwrap();
+        for key in ["WAIT_WATCH", "WAIT_WATCH_SIGNAL", "WAIT_WATCH_INTERVAL"] {
+            env_reader::set_override(key, "".to_string());
+        }
+
+        assert_eq!(
+            Some(watch::Watch {
+                action: watch::Action::Signal(libc::SIGUSR1),
+                interval: 5,
+                failure_threshold: 3,
+                kill_timeout: 10,
+            }),
+            config.watch
+        );
+    }
+
+    #[test]
+    fn should_fail_when_given_an_invalid_command() {
         let _guard = TEST_MUTEX.lock().unwrap();
         set_env("", "", "", "", "", "", "a 'b");
-        config_from_env();
+        assert!(
+            config_from_env()
+                .err()
+                .is_some_and(|e| e.starts_with("failed to parse the value of [WAIT_COMMAND]"))
+        );
+    }
+
+    #[test]
+    fn should_fail_when_given_an_invalid_target() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        set_env("atleast(3; db:5432)", "", "", "", "", "", "");
+        assert!(
+            config_from_env()
+                .err()
+                .is_some_and(|e| e.starts_with("failed to parse the targets"))
+        );
     }
 
     fn set_env(
@@ -0,0 +1,115 @@
+use log::kv::{self, VisitSource};
+use serde_json::{Map, Value, json};
+use std::io::Write;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static STARTED_AT: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+pub fn format_from_str(format: &str) -> Format {
+    match format.trim().to_lowercase().as_str() {
+        "json" => Format::Json,
+        _ => Format::Text,
+    }
+}
+
+pub fn init(level: &str, format: Format) {
+    let mut builder = env_logger::Builder::new();
+    builder.parse_filters(level);
+    if format == Format::Json {
+        JSON.store(true, Ordering::Relaxed);
+        LazyLock::force(&STARTED_AT);
+        builder.format(|buf, record| writeln!(buf, "{}", to_json(record)));
+    }
+    builder.init();
+}
+
+// Decorations, like the separator lines, are only meant for humans
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+// One object per event, with the key-values of the record as fields
+fn to_json(record: &log::Record) -> Value {
+    let mut fields = Map::new();
+    fields.insert(
+        "timestamp_ms".to_string(),
+        json!(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or(0)
+        ),
+    );
+    fields.insert(
+        "elapsed_ms".to_string(),
+        json!(STARTED_AT.elapsed().as_millis() as u64),
+    );
+    fields.insert("level".to_string(), json!(record.level().as_str()));
+    fields.insert("message".to_string(), json!(record.args().to_string()));
+    let _ = record.key_values().visit(&mut Fields(&mut fields));
+    Value::Object(fields)
+}
+
+struct Fields<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(value) = value.to_u64() {
+            json!(value)
+        } else if let Some(value) = value.to_i64() {
+            json!(value)
+        } else if let Some(value) = value.to_bool() {
+            json!(value)
+        } else {
+            json!(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_the_format() {
+        assert_eq!(Format::Text, format_from_str(""));
+        assert_eq!(Format::Text, format_from_str("text"));
+        assert_eq!(Format::Json, format_from_str(" JSON "));
+    }
+
+    #[test]
+    fn should_format_a_record_as_json() {
+        let kvs: &[(&str, kv::Value)] = &[
+            ("event", kv::Value::from("not_available")),
+            ("target", kv::Value::from("db:5432")),
+            ("attempt", kv::Value::from(3u64)),
+        ];
+        let json = to_json(
+            &log::Record::builder()
+                .level(log::Level::Info)
+                .args(format_args!("host [db:5432] not yet available..."))
+                .key_values(&kvs)
+                .build(),
+        );
+
+        assert_eq!("INFO", json["level"]);
+        assert_eq!("host [db:5432] not yet available...", json["message"]);
+        assert_eq!("not_available", json["event"]);
+        assert_eq!("db:5432", json["target"]);
+        assert_eq!(3, json["attempt"]);
+        assert!(json["elapsed_ms"].is_u64());
+        assert!(json["timestamp_ms"].is_u64());
+    }
+}
@@ -1,10 +1,87 @@
+use log::*;
+use wait::cli::Subcommand;
+use wait::exit_code;
+
 fn main() {
-    env_logger::init_from_env(env_logger::Env::default().filter_or("WAIT_LOGGER_LEVEL", "debug"));
+    let (subcommand, args) = wait::cli::parse_subcommand(std::env::args().skip(1));
+    let options = wait::cli::parse_args(args);
+    let invalid_configuration = match subcommand {
+        Subcommand::Check => exit_code::UNHEALTHY,
+        _ => exit_code::INVALID_CONFIGURATION,
+    };
+    if let Ok(options) = &options {
+        for (key, value) in options {
+            wait::env_reader::set_override(key, value.clone());
+        }
+    }
 
-    let mut sleep = wait::sleeper::new();
-    wait::wait(&mut sleep, &wait::config_from_env(), &mut on_timeout);
-}
+    // The logger is needed to report the invalid values, including its own ones
+    let level = wait::env_reader::try_env_var("WAIT_LOGGER_LEVEL", "debug".to_string());
+    let format = wait::env_reader::try_env_var("WAIT_LOG_FORMAT", "".to_string());
+    wait::logger::init(
+        level.as_deref().unwrap_or("debug"),
+        wait::logger::format_from_str(format.as_deref().unwrap_or("")),
+    );
+    if let Some(e) = options.err().or(level.err()).or(format.err()) {
+        error!("{}", e);
+        std::process::exit(invalid_configuration);
+    }
+
+    wait::signal::exit_on_signals();
 
-fn on_timeout() {
-    std::process::exit(1);
+    let config = match wait::config_from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid configuration: {}", e);
+            std::process::exit(invalid_configuration);
+        }
+    };
+    match subcommand {
+        Subcommand::Wait => {
+            let mut sleep = wait::sleeper::new();
+            if let Err(e) = wait::wait(&mut sleep, &config, &mut || {
+                std::process::exit(config.timeout_exit_code)
+            }) {
+                error!("{}", e);
+                std::process::exit(exit_code::INVALID_CONFIGURATION);
+            }
+        }
+        // Waits like the default subcommand, then keeps the barrier open until terminated
+        Subcommand::Serve => {
+            if config.barrier_addr.is_none() {
+                error!("Invalid configuration: the serve subcommand requires WAIT_BARRIER_ADDR");
+                std::process::exit(exit_code::INVALID_CONFIGURATION);
+            }
+            let timeout_exit_code = config.timeout_exit_code;
+            let config = wait::Config {
+                command: None,
+                watch: None,
+                ..config
+            };
+            let mut sleep = wait::sleeper::new();
+            if let Err(e) = wait::wait(&mut sleep, &config, &mut || {
+                std::process::exit(timeout_exit_code)
+            }) {
+                error!("{}", e);
+                std::process::exit(exit_code::INVALID_CONFIGURATION);
+            }
+            // Nothing is waited for, so there is no barrier to keep open
+            if config.explain.is_some() {
+                std::process::exit(0);
+            }
+            loop {
+                std::thread::park();
+            }
+        }
+        Subcommand::Check => match wait::check(&config) {
+            Ok((healthy, status)) => {
+                println!("{}", status);
+                std::process::exit(if healthy { 0 } else { exit_code::UNHEALTHY });
+            }
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(invalid_configuration);
+            }
+        },
+    }
 }
@@ -0,0 +1,91 @@
+use log::*;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_CONTENT: &str = "ready at {timestamp}: {targets}\n";
+
+// A file written once all the targets are available, so that sibling containers
+// sharing a volume can wait for it instead of repeating the same checks
+#[derive(Clone)]
+pub struct Marker {
+    pub path: String,
+    pub content: String,
+}
+
+impl Marker {
+    // The file is written next to its final path and then renamed,
+    // so that readers never see it partially written.
+    pub fn write(&self, targets: &[String], skipped: &[String]) -> std::io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let temp_path = format!("{}.tmp", self.path);
+        fs::write(
+            &temp_path,
+            render(&self.content, timestamp, targets, skipped),
+        )?;
+        fs::rename(&temp_path, &self.path)?;
+        info!("Marker file [{}] written", self.path);
+        Ok(())
+    }
+
+    pub fn remove(&self) {
+        match fs::remove_file(&self.path) {
+            Ok(()) => info!("Marker file [{}] removed", self.path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove marker file [{}]: {}", self.path, e),
+        }
+    }
+}
+
+// Replaces the `{timestamp}`, `{targets}` and `{skipped}` placeholders of the template
+fn render(template: &str, timestamp: u64, targets: &[String], skipped: &[String]) -> String {
+    template
+        .replace("{timestamp}", &timestamp.to_string())
+        .replace("{targets}", &targets.join(", "))
+        .replace("{skipped}", &skipped.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_render_the_template() {
+        assert_eq!(
+            "ready at 1234: host [db:5432], path [/tmp/a]\n",
+            render(
+                DEFAULT_CONTENT,
+                1234,
+                &["host [db:5432]".to_string(), "path [/tmp/a]".to_string()],
+                &[]
+            )
+        );
+        assert_eq!(
+            "{\"skipped\": \"host [jaeger:4317]\"}",
+            render(
+                "{\"skipped\": \"{skipped}\"}",
+                1234,
+                &[],
+                &["host [jaeger:4317]".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn should_write_and_remove_the_marker() {
+        let marker = Marker {
+            path: format!("./target/{}.ready", rand::random::<u64>()),
+            content: "{targets}".to_string(),
+        };
+        marker.write(&["path [./target]".to_string()], &[]).unwrap();
+        assert_eq!("path [./target]", fs::read_to_string(&marker.path).unwrap());
+        assert!(!std::path::Path::new(&format!("{}.tmp", marker.path)).exists());
+
+        marker.remove();
+        assert!(!std::path::Path::new(&marker.path).exists());
+        marker.remove();
+    }
+}
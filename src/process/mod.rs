@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::Path;
+
+const PROC_DIR: &str = "/proc";
+
+pub fn is_running(name: &str) -> bool {
+    let name = name.trim();
+    if name.is_empty() {
+        return false;
+    }
+    let own_pid = std::process::id();
+    match fs::read_dir(PROC_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|pid| *pid != own_pid)
+            .any(|pid| process_matches(pid, name) && is_alive(pid)),
+        Err(_e) => false,
+    }
+}
+
+pub fn is_alive(pid: u32) -> bool {
+    match fs::read_to_string(format!("{}/{}/stat", PROC_DIR, pid)) {
+        // The state is the first field after the process name, which is enclosed
+        // in parentheses and may itself contain spaces or parentheses.
+        Ok(stat) => match stat.rfind(')') {
+            Some(index) => !matches!(
+                stat[index + 1..].trim_start().chars().next(),
+                Some('Z' | 'X' | 'x') | None
+            ),
+            None => false,
+        },
+        Err(_e) => false,
+    }
+}
+
+pub fn pid_from_file(path: &str) -> Option<u32> {
+    match fs::read_to_string(path.trim()) {
+        Ok(content) => content.trim().parse::<u32>().ok().filter(|pid| *pid > 0),
+        Err(_e) => None,
+    }
+}
+
+pub fn is_pidfile_alive(path: &str) -> bool {
+    match pid_from_file(path) {
+        Some(pid) => is_alive(pid),
+        None => false,
+    }
+}
+
+fn process_matches(pid: u32, name: &str) -> bool {
+    if let Ok(comm) = fs::read_to_string(format!("{}/{}/comm", PROC_DIR, pid))
+        && comm.trim_end_matches('\n') == name
+    {
+        return true;
+    }
+    match fs::read(format!("{}/{}/cmdline", PROC_DIR, pid)) {
+        Ok(cmdline) => {
+            let argv: Vec<String> = cmdline
+                .split(|byte| *byte == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).to_string())
+                .collect();
+            match argv.first() {
+                Some(program) => {
+                    program == name
+                        || Path::new(program)
+                            .file_name()
+                            .is_some_and(|file_name| file_name == name)
+                        || argv.join(" ") == name
+                }
+                None => false,
+            }
+        }
+        Err(_e) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use std::process::Command;
+
+    #[test]
+    fn should_find_a_running_process_by_name() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let running = eventually(|| is_running("sleep"));
+        let running_by_cmdline = eventually(|| is_running("sleep 5"));
+        let alive = is_alive(child.id());
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(running);
+        assert!(running_by_cmdline);
+        assert!(alive);
+    }
+
+    #[test]
+    fn should_not_find_a_missing_process() {
+        assert!(!is_running("fhsdjkfhsjkdfhskdjfhskdjfhskjdf"));
+        assert!(!is_running(""));
+    }
+
+    #[test]
+    fn should_not_match_the_current_process() {
+        let name = std::env::current_exe().unwrap();
+        assert!(!is_running(name.to_str().unwrap()));
+    }
+
+    #[test]
+    fn should_detect_a_dead_process() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!is_alive(pid));
+    }
+
+    #[test]
+    fn should_read_the_pid_from_a_file() {
+        let path = format!("./target/{}.pid", rand::random::<u64>());
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{}", std::process::id()).unwrap();
+
+        assert_eq!(Some(std::process::id()), pid_from_file(&path));
+        assert!(is_pidfile_alive(&path));
+    }
+
+    #[test]
+    fn should_not_be_alive_if_the_pidfile_is_invalid() {
+        let path = format!("./target/{}.pid", rand::random::<u64>());
+        assert!(!is_pidfile_alive(&path));
+
+        File::create(&path).unwrap().write_all(b"abc").unwrap();
+        assert_eq!(None, pid_from_file(&path));
+        assert!(!is_pidfile_alive(&path));
+    }
+
+    // The command line of a freshly spawned process could not be visible yet
+    fn eventually(check: impl Fn() -> bool) -> bool {
+        (0..100).any(|_| {
+            let result = check();
+            if !result {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            result
+        })
+    }
+}
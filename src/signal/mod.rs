@@ -0,0 +1,108 @@
+use crate::exit_code;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Signals that stop the wait, or that are forwarded to the command in watch mode
+const HANDLED_SIGNALS: &[i32] = &[
+    libc::SIGTERM,
+    libc::SIGINT,
+    libc::SIGHUP,
+    libc::SIGQUIT,
+    libc::SIGUSR1,
+    libc::SIGUSR2,
+];
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("TERM", libc::SIGTERM),
+];
+
+// One bit per signal number, so that quick signals of different kinds are not lost
+static RECEIVED_SIGNALS: AtomicU64 = AtomicU64::new(0);
+
+// Signals are accepted by number or by name, with or without the `SIG` prefix
+pub fn signal_from_str(signal: &str) -> Option<i32> {
+    let signal = signal.trim().to_uppercase();
+    if let Ok(number) = signal.parse::<i32>() {
+        return Some(number).filter(|number| *number > 0);
+    }
+    let name = signal.strip_prefix("SIG").unwrap_or(&signal);
+    SIGNALS
+        .iter()
+        .find(|(signal_name, _)| *signal_name == name)
+        .map(|(_, number)| *number)
+}
+
+// As the init process of a container, the wait process would otherwise ignore
+// the signals sent by e.g. `docker stop` and be killed only after a grace period.
+pub fn exit_on_signals() {
+    set_handler(on_exit_signal);
+}
+
+// Once the command is started, the wait process stays in the foreground as its supervisor,
+// so the signals sent to the container must reach the command.
+pub fn forward_signals() {
+    set_handler(on_forwarded_signal);
+}
+
+// Returns the signals received since the last call, by number
+pub fn take_received() -> Vec<i32> {
+    to_signals(RECEIVED_SIGNALS.swap(0, Ordering::SeqCst))
+}
+
+fn to_bit(signal: i32) -> u64 {
+    if (1..64).contains(&signal) {
+        1 << signal
+    } else {
+        0
+    }
+}
+
+fn to_signals(bits: u64) -> Vec<i32> {
+    (1..64)
+        .filter(|signal| bits & to_bit(*signal) != 0)
+        .collect()
+}
+
+fn set_handler(handler: extern "C" fn(i32)) {
+    for signal in HANDLED_SIGNALS {
+        // SAFETY: the handlers only call async-signal-safe functions
+        unsafe {
+            libc::signal(*signal, handler as *const () as libc::sighandler_t);
+        }
+    }
+}
+
+extern "C" fn on_exit_signal(signal: i32) {
+    // SAFETY: `_exit` is async-signal-safe
+    unsafe { libc::_exit(exit_code::SIGNAL_BASE + signal) }
+}
+
+extern "C" fn on_forwarded_signal(signal: i32) {
+    RECEIVED_SIGNALS.fetch_or(to_bit(signal), Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_the_signal() {
+        assert_eq!(Some(libc::SIGHUP), signal_from_str("HUP"));
+        assert_eq!(Some(libc::SIGUSR1), signal_from_str(" sigusr1 "));
+        assert_eq!(Some(9), signal_from_str("9"));
+        assert_eq!(None, signal_from_str("0"));
+        assert_eq!(None, signal_from_str("SIGWHATEVER"));
+    }
+
+    #[test]
+    fn should_keep_every_received_signal() {
+        let bits = to_bit(libc::SIGTERM) | to_bit(libc::SIGINT) | to_bit(libc::SIGTERM);
+        assert_eq!(vec![libc::SIGINT, libc::SIGTERM], to_signals(bits));
+        assert!(to_signals(to_bit(0) | to_bit(64)).is_empty());
+    }
+}
@@ -5,6 +5,7 @@ pub trait Sleeper {
     fn sleep(&self, duration: u64);
     fn reset(&mut self);
     fn elapsed(&self, units: u64) -> bool;
+    fn elapsed_units(&self) -> u64;
 }
 
 struct SecondsSleeper {
@@ -31,6 +32,10 @@ impl Sleeper for SecondsSleeper {
     fn elapsed(&self, units: u64) -> bool {
         self.started_at.elapsed().as_secs() >= units
     }
+
+    fn elapsed_units(&self) -> u64 {
+        self.started_at.elapsed().as_secs()
+    }
 }
 
 pub struct MillisSleeper {
@@ -57,6 +62,10 @@ impl Sleeper for MillisSleeper {
     fn elapsed(&self, units: u64) -> bool {
         self.started_at.elapsed().as_millis() >= u128::from(units)
     }
+
+    fn elapsed_units(&self) -> u64 {
+        self.started_at.elapsed().as_millis() as u64
+    }
 }
 
 struct NoOpsSleeper {}
@@ -69,6 +78,10 @@ impl Sleeper for NoOpsSleeper {
     fn elapsed(&self, _units: u64) -> bool {
         true
     }
+
+    fn elapsed_units(&self) -> u64 {
+        u64::MAX
+    }
 }
 
 pub fn new() -> impl Sleeper {
@@ -105,6 +118,18 @@ mod test {
 
         sleeper.sleep(1);
         assert!(sleeper.elapsed(2));
+        assert_eq!(2, sleeper.elapsed_units());
+    }
+
+    #[test]
+    fn should_count_the_elapsed_millis() {
+        let mut sleeper = MillisSleeper::default();
+        sleeper.reset();
+        assert!(sleeper.elapsed_units() < 10);
+
+        sleeper.sleep(20);
+        assert!(sleeper.elapsed_units() >= 20);
+        assert!(sleeper.elapsed(20));
     }
 
     #[test]
@@ -115,5 +140,7 @@ mod test {
         sleeper.sleep(10);
         let elapsed_sec = start.elapsed().as_secs();
         assert!(elapsed_sec <= 1);
+        assert!(sleeper.elapsed(10));
+        assert_eq!(u64::MAX, sleeper.elapsed_units());
     }
 }
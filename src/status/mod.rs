@@ -0,0 +1,361 @@
+use crate::target::TargetSpec;
+use log::*;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    NotStarted,
+    Waiting,
+    Available,
+    Unavailable,
+    Skipped,
+    TimedOut,
+}
+
+impl State {
+    fn name(&self) -> &'static str {
+        match self {
+            State::NotStarted => "not_started",
+            State::Waiting => "waiting",
+            State::Available => "available",
+            State::Unavailable => "unavailable",
+            State::Skipped => "skipped",
+            State::TimedOut => "timed_out",
+        }
+    }
+}
+
+struct TargetStatus {
+    kind: &'static str,
+    name: String,
+    optional: bool,
+    state: State,
+    attempts: u64,
+    last_error: Option<String>,
+    last_success: Option<Instant>,
+}
+
+impl TargetStatus {
+    fn describe(&self) -> String {
+        let state = match self.state {
+            State::Available => "available",
+            State::Skipped => "skipped",
+            State::TimedOut => "timed out",
+            State::NotStarted => "not started",
+            State::Waiting | State::Unavailable => "not available",
+        };
+        let error = match (self.state, &self.last_error) {
+            (State::Available, _) | (_, None) => "".to_string(),
+            (_, Some(error)) => format!(" ({})", error),
+        };
+        let last_success = match self.last_success {
+            Some(instant) => format!("last available {} seconds ago", instant.elapsed().as_secs()),
+            None => "never available".to_string(),
+        };
+        format!(
+            "{} [{}]: {}{}, {} attempts, {}",
+            self.kind, self.name, state, error, self.attempts, last_success
+        )
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    targets: Vec<TargetStatus>,
+    completed: bool,
+}
+
+// The state of the targets, shared with the HTTP listener
+#[derive(Clone, Default)]
+pub struct Status {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Status {
+    // Adds the targets to the status with the given initial state, returning the index of the first one
+    pub fn add(&self, specs: &[TargetSpec], state: State) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        let first = inner.targets.len();
+        inner.targets.extend(specs.iter().map(|spec| TargetStatus {
+            kind: spec.target.kind(),
+            name: spec.target.name().to_string(),
+            optional: spec.optional,
+            state,
+            attempts: 0,
+            last_error: None,
+            last_success: None,
+        }));
+        first
+    }
+
+    // Records the outcome of a check of the target with the given index.
+    // A successful check makes the target available only once the caller says so,
+    // e.g. after the consecutive successful checks of its success threshold.
+    pub fn checked(&self, index: usize, result: &Result<(), String>, available: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(target) = inner.targets.get_mut(index) {
+            target.attempts += 1;
+            match result {
+                Ok(()) => {
+                    if available {
+                        target.state = State::Available;
+                    }
+                    target.last_success = Some(Instant::now());
+                }
+                Err(e) => {
+                    if target.state == State::Available {
+                        target.state = State::Unavailable;
+                    }
+                    target.last_error = Some(e.clone());
+                }
+            }
+        }
+    }
+
+    pub fn set_state(&self, index: usize, state: State) {
+        if let Some(target) = self.inner.lock().unwrap().targets.get_mut(index) {
+            target.state = state;
+        }
+    }
+
+    pub fn set_completed(&self) {
+        self.inner.lock().unwrap().completed = true;
+    }
+
+    // Ready once the wait is completed and while all the required targets are available
+    pub fn is_ready(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.completed
+            && inner
+                .targets
+                .iter()
+                .all(|target| target.optional || target.state == State::Available)
+    }
+
+    // Logs the last state of every target, e.g. to find out why the wait timed out
+    pub fn log_report(&self) {
+        let inner = self.inner.lock().unwrap();
+        error!(event = "report"; "State of the targets:");
+        for target in &inner.targets {
+            error!(
+                event = "report", target = target.name.as_str(), kind = target.kind,
+                state = target.state.name(), attempts = target.attempts,
+                error = target.last_error.as_deref().unwrap_or("");
+                " - {}", target.describe()
+            );
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let ready = self.is_ready();
+        let inner = self.inner.lock().unwrap();
+        json!({
+            "ready": ready,
+            "completed": inner.completed,
+            "targets": inner
+                .targets
+                .iter()
+                .map(|target| json!({
+                    "kind": target.kind,
+                    "name": target.name,
+                    "optional": target.optional,
+                    "state": target.state.name(),
+                    "attempts": target.attempts,
+                    "last_error": target.last_error,
+                }))
+                .collect::<Vec<Value>>(),
+        })
+    }
+}
+
+// Serves `/status` and `/ready` in a background thread, returning the bound address
+pub fn serve(addr: &str, status: Status) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = handle(stream, &status) {
+                        debug!("Failed to serve a status request: {}", e);
+                    }
+                }
+                Err(e) => debug!("Failed to accept a status connection: {}", e),
+            }
+        }
+    });
+    Ok(local_addr)
+}
+
+fn handle(mut stream: TcpStream, status: &Status) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (code, body) = match path.split('?').next().unwrap_or("") {
+        "/status" => ("200 OK", status.to_json()),
+        "/ready" if status.is_ready() => ("200 OK", json!({ "ready": true })),
+        "/ready" => ("503 Service Unavailable", json!({ "ready": false })),
+        _ => ("404 Not Found", json!({ "error": "not found" })),
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::{Config, target};
+    use std::io::Read;
+
+    fn get(addr: SocketAddr, path: &str) -> (String, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (
+            head.lines().next().unwrap().to_string(),
+            serde_json::from_str(body).unwrap(),
+        )
+    }
+
+    fn status() -> Status {
+        let config = Config {
+            hosts: "db:5432".to_string(),
+            paths: "/tmp/a?optional".to_string(),
+            ..Default::default()
+        };
+        let status = Status::default();
+        assert_eq!(
+            0,
+            status.add(&target::from_config(&config).unwrap(), State::Waiting)
+        );
+        status
+    }
+
+    #[test]
+    fn should_track_the_state_of_the_targets() {
+        let status = status();
+        status.checked(0, &Err("connection failed".to_string()), false);
+        status.checked(0, &Ok(()), true);
+        status.checked(1, &Err("path missing".to_string()), false);
+        status.set_state(1, State::Skipped);
+
+        let json = status.to_json();
+        assert_eq!(false, json["ready"]);
+        assert_eq!("db:5432", json["targets"][0]["name"]);
+        assert_eq!("host", json["targets"][0]["kind"]);
+        assert_eq!("available", json["targets"][0]["state"]);
+        assert_eq!(2, json["targets"][0]["attempts"]);
+        assert_eq!("connection failed", json["targets"][0]["last_error"]);
+        assert_eq!("skipped", json["targets"][1]["state"]);
+        assert_eq!(true, json["targets"][1]["optional"]);
+
+        status.set_completed();
+        assert!(status.is_ready());
+
+        status.checked(0, &Err("connection failed".to_string()), false);
+        assert_eq!("unavailable", status.to_json()["targets"][0]["state"]);
+        assert!(!status.is_ready());
+    }
+
+    #[test]
+    fn should_wait_for_the_availability_decision() {
+        let status = status();
+        status.checked(1, &Ok(()), true);
+        status.set_completed();
+
+        status.checked(0, &Ok(()), false);
+        status.checked(0, &Ok(()), false);
+        assert_eq!("waiting", status.to_json()["targets"][0]["state"]);
+        assert_eq!(2, status.to_json()["targets"][0]["attempts"]);
+        assert!(!status.is_ready());
+
+        status.checked(0, &Ok(()), true);
+        assert_eq!("available", status.to_json()["targets"][0]["state"]);
+        assert!(status.is_ready());
+    }
+
+    #[test]
+    fn should_describe_the_state_of_the_targets() {
+        let status = status();
+        status.checked(0, &Ok(()), true);
+        status.checked(0, &Err("connection refused".to_string()), false);
+        status.set_state(0, State::TimedOut);
+        status.checked(1, &Err("path missing".to_string()), false);
+
+        let inner = status.inner.lock().unwrap();
+        assert_eq!(
+            "host [db:5432]: timed out (connection refused), 2 attempts, last available 0 seconds ago",
+            inner.targets[0].describe()
+        );
+        assert_eq!(
+            "path [/tmp/a]: not available (path missing), 1 attempts, never available",
+            inner.targets[1].describe()
+        );
+    }
+
+    #[test]
+    fn should_show_the_targets_not_started() {
+        let status = status();
+        let config = Config {
+            paths: "/tmp/b".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            2,
+            status.add(&target::from_config(&config).unwrap(), State::NotStarted)
+        );
+        status.checked(0, &Ok(()), true);
+        status.checked(1, &Ok(()), true);
+        status.set_completed();
+
+        let json = status.to_json();
+        assert_eq!(false, json["ready"]);
+        assert_eq!("/tmp/b", json["targets"][2]["name"]);
+        assert_eq!("not_started", json["targets"][2]["state"]);
+        assert_eq!(
+            "path [/tmp/b]: not started, 0 attempts, never available",
+            status.inner.lock().unwrap().targets[2].describe()
+        );
+    }
+
+    #[test]
+    fn should_serve_the_status() {
+        let status = status();
+        let addr = serve("127.0.0.1:0", status.clone()).unwrap();
+
+        let (code, body) = get(addr, "/ready");
+        assert_eq!("HTTP/1.1 503 Service Unavailable", code);
+        assert_eq!(false, body["ready"]);
+
+        let (code, body) = get(addr, "/status");
+        assert_eq!("HTTP/1.1 200 OK", code);
+        assert_eq!("waiting", body["targets"][0]["state"]);
+
+        status.checked(0, &Ok(()), true);
+        status.set_completed();
+        let (code, body) = get(addr, "/ready?verbose");
+        assert_eq!("HTTP/1.1 200 OK", code);
+        assert_eq!(true, body["ready"]);
+
+        let (code, _) = get(addr, "/other");
+        assert_eq!("HTTP/1.1 404 Not Found", code);
+    }
+}
@@ -0,0 +1,909 @@
+use crate::{Command, Config, Stage, command, kubernetes, parse_command};
+use log::*;
+use std::fmt;
+use std::io::ErrorKind;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+const PIDFILE_PREFIX: &str = "pidfile:";
+const DOCKER_PREFIX: &str = "docker://";
+const KUBERNETES_PREFIX: &str = "k8s://";
+const MAX_REASON_LENGTH: usize = 200;
+const OPTIONS_SEPARATOR: char = '?';
+const SUCCESS_THRESHOLD_OPTION: &str = "success_threshold";
+const TIMEOUT_OPTION: &str = "timeout";
+const INTERVAL_OPTION: &str = "interval";
+const OPTIONAL_OPTION: &str = "optional";
+const ANY_GROUP: &str = "any";
+const QUORUM_GROUP: &str = "quorum";
+const AT_LEAST_GROUP: &str = "atleast";
+const OPTIONS: &[&str] = &[
+    SUCCESS_THRESHOLD_OPTION,
+    TIMEOUT_OPTION,
+    INTERVAL_OPTION,
+    OPTIONAL_OPTION,
+];
+
+#[derive(Clone)]
+pub enum Target {
+    Host(String),
+    Path(String),
+    Process(String),
+    PidFile(String),
+    Command(Command, String),
+    Docker(String),
+    Kubernetes(kubernetes::Object, String),
+    Group {
+        name: String,
+        required: usize,
+        members: Vec<Target>,
+    },
+}
+
+impl Target {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Target::Host(_) => "host",
+            Target::Path(_) => "path",
+            Target::Process(_) => "process",
+            Target::PidFile(_) => "pidfile",
+            Target::Command(_, _) => "command",
+            Target::Docker(_) => "docker",
+            Target::Kubernetes(_, _) => "k8s",
+            Target::Group { .. } => "group",
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Target::Host(name)
+            | Target::Path(name)
+            | Target::Process(name)
+            | Target::PidFile(name)
+            | Target::Command(_, name)
+            | Target::Docker(name)
+            | Target::Kubernetes(_, name)
+            | Target::Group { name, .. } => name,
+        }
+    }
+
+    pub fn is_available(&self, config: &Config) -> bool {
+        self.check(config).is_ok()
+    }
+
+    // Returns the reason why the target is not available, if any
+    pub fn check(&self, config: &Config) -> Result<(), String> {
+        match self {
+            Target::Host(host) => connect(host, Duration::from_secs(config.tcp_connection_timeout)),
+            Target::Path(path) => {
+                if Path::new(path).exists() {
+                    Ok(())
+                } else {
+                    Err("path missing".to_string())
+                }
+            }
+            Target::Process(name) => {
+                if crate::process::is_running(name) {
+                    Ok(())
+                } else {
+                    Err("process not running".to_string())
+                }
+            }
+            Target::PidFile(path) => {
+                if crate::process::is_pidfile_alive(path) {
+                    Ok(())
+                } else {
+                    Err("process of the PID file not running".to_string())
+                }
+            }
+            Target::Command(command, command_string) => {
+                match command::run_with_timeout(
+                    command,
+                    Duration::from_secs(config.check_command_timeout),
+                ) {
+                    Ok(outcome) if outcome.exit_code == Some(config.check_command_exit_code) => {
+                        Ok(())
+                    }
+                    Ok(outcome) => {
+                        if outcome.timed_out {
+                            debug!(
+                                "Command [{}] killed after {} seconds. Output: [{}]",
+                                command_string, config.check_command_timeout, outcome.output
+                            );
+                            Err(format!(
+                                "killed after {} seconds",
+                                config.check_command_timeout
+                            ))
+                        } else {
+                            debug!(
+                                "Command [{}] exited with code {:?}. Output: [{}]",
+                                command_string, outcome.exit_code, outcome.output
+                            );
+                            let reason = match outcome.exit_code {
+                                Some(code) => format!("exited with code {}", code),
+                                None => "killed by a signal".to_string(),
+                            };
+                            Err(match outcome.output.lines().last() {
+                                Some(line) => format!("{}: {}", reason, truncate(line)),
+                                None => reason,
+                            })
+                        }
+                    }
+                    Err(e) => {
+                        debug!("Command [{}] failed to start: {}", command_string, e);
+                        Err(format!("failed to start: {}", e))
+                    }
+                }
+            }
+            Target::Docker(container) => crate::docker::check(
+                &config.docker_socket,
+                container,
+                Duration::from_secs(config.tcp_connection_timeout),
+            ),
+            Target::Kubernetes(object, _) => kubernetes::check(
+                &config.kubernetes,
+                object,
+                Duration::from_secs(config.tcp_connection_timeout),
+            ),
+            Target::Group {
+                required, members, ..
+            } => {
+                let available: Vec<&str> = members
+                    .iter()
+                    .filter(|member| member.is_available(config))
+                    .map(|member| member.name())
+                    .collect();
+                if available.len() >= *required {
+                    info!("{} satisfied by [{}]", self, available.join(", "));
+                    Ok(())
+                } else {
+                    debug!(
+                        "{} has {} of the {} required members available: [{}]",
+                        self,
+                        available.len(),
+                        required,
+                        available.join(", ")
+                    );
+                    Err(format!(
+                        "{} of the {} required members available",
+                        available.len(),
+                        required
+                    ))
+                }
+            }
+        }
+    }
+}
+
+// Tries every address the host resolves to, returning the reason of the last failure
+fn connect(host: &str, timeout: Duration) -> Result<(), String> {
+    let addrs = match host.to_socket_addrs() {
+        Ok(addrs) => addrs,
+        Err(e) => return Err(format!("DNS failure: {}", e)),
+    };
+    let mut error = "DNS failure: no addresses found".to_string();
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                error = match e.kind() {
+                    ErrorKind::ConnectionRefused => "connection refused".to_string(),
+                    ErrorKind::TimedOut | ErrorKind::WouldBlock => {
+                        "connection timed out".to_string()
+                    }
+                    _ => format!("connection failed: {}", e),
+                }
+            }
+        }
+    }
+    Err(error)
+}
+
+// Keeps the reasons of the failures short enough for a log line
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_REASON_LENGTH) {
+        Some((index, _)) => format!("{}...", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.kind(), self.name())
+    }
+}
+
+#[derive(Clone)]
+pub struct TargetSpec {
+    pub target: Target,
+    pub success_threshold: u64,
+    pub timeout: u64,
+    pub interval: u64,
+    pub optional: bool,
+}
+
+impl fmt::Display for TargetSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.target.fmt(f)
+    }
+}
+
+pub fn from_config(config: &Config) -> Result<Vec<TargetSpec>, String> {
+    from_lists(
+        [
+            &config.hosts,
+            &config.paths,
+            &config.processes,
+            &config.check_commands,
+        ],
+        config.global_timeout,
+        config,
+    )
+}
+
+// The targets of the main configuration followed by the ones of all the stages
+pub fn from_config_and_stages(config: &Config) -> Result<Vec<TargetSpec>, String> {
+    let mut specs = from_config(config)?;
+    for stage in &config.stages {
+        specs.extend(from_stage(stage, config)?);
+    }
+    Ok(specs)
+}
+
+pub fn from_stage(stage: &Stage, config: &Config) -> Result<Vec<TargetSpec>, String> {
+    from_lists(
+        [
+            &stage.hosts,
+            &stage.paths,
+            &stage.processes,
+            &stage.check_commands,
+        ],
+        stage
+            .timeout
+            .unwrap_or(config.global_timeout)
+            .min(config.global_timeout),
+        config,
+    )
+}
+
+fn from_lists(
+    [hosts, paths, processes, check_commands]: [&str; 4],
+    timeout: u64,
+    config: &Config,
+) -> Result<Vec<TargetSpec>, String> {
+    let mut specs = vec![];
+    specs.extend(parse_list(hosts, timeout, config, false, &|host| {
+        if let Some(container) = host.strip_prefix(DOCKER_PREFIX) {
+            return Ok(Target::Docker(container.trim().to_string()));
+        }
+        match host.strip_prefix(KUBERNETES_PREFIX) {
+            Some(object) => Ok(Target::Kubernetes(
+                kubernetes::parse(object)?,
+                object.trim().to_string(),
+            )),
+            None => Ok(Target::Host(host)),
+        }
+    })?);
+    specs.extend(parse_list(paths, timeout, config, false, &|path| {
+        Ok(Target::Path(path))
+    })?);
+    specs.extend(parse_list(processes, timeout, config, false, &|process| {
+        Ok(match process.strip_prefix(PIDFILE_PREFIX) {
+            Some(path) => Target::PidFile(path.trim().to_string()),
+            None => Target::Process(process),
+        })
+    })?);
+    specs.extend(parse_list(
+        check_commands,
+        timeout,
+        config,
+        true,
+        &|check_command| match parse_command(check_command) {
+            Ok(Some((command, command_string))) => Ok(Target::Command(command, command_string)),
+            Ok(None) => Err("empty check command".to_string()),
+            Err(e) => Err(format!("failed to parse check command: {}", e)),
+        },
+    )?);
+    Ok(specs)
+}
+
+type EntryParser = dyn Fn(String) -> Result<Target, String>;
+
+// Unknown options are only kept as part of the entries of the lists that allow them,
+// e.g. the query string of an URL in a check command
+fn parse_list(
+    list: &str,
+    timeout: u64,
+    config: &Config,
+    keep_unknown_options: bool,
+    parser: &EntryParser,
+) -> Result<Vec<TargetSpec>, String> {
+    let mut specs = vec![];
+    for entry in split(list) {
+        let (entry, options) = match split_options(&entry) {
+            Err(_e) if keep_unknown_options => (entry, vec![]),
+            result => result?,
+        };
+        specs.push(new_spec(
+            parse_entry(entry, parser)?,
+            &options,
+            timeout,
+            config,
+        ));
+    }
+    Ok(specs)
+}
+
+fn parse_entry(entry: String, parser: &EntryParser) -> Result<Target, String> {
+    match parse_group(&entry, parser)? {
+        Some(group) => Ok(group),
+        None => parser(entry),
+    }
+}
+
+// Groups are in the form `any(a, b, c)`, `quorum(a, b, c)` or `atleast(2; a, b, c)`
+fn parse_group(entry: &str, parser: &EntryParser) -> Result<Option<Target>, String> {
+    let (function, arguments) = match entry.split_once('(') {
+        Some((function, arguments)) if entry.ends_with(')') => (
+            function.trim().to_lowercase(),
+            &arguments[..arguments.len() - 1],
+        ),
+        _ => return Ok(None),
+    };
+    let (required, arguments) = match function.as_str() {
+        ANY_GROUP => (Some(1), arguments),
+        QUORUM_GROUP => (None, arguments),
+        AT_LEAST_GROUP => match arguments.split_once(';') {
+            Some((required, arguments)) => match required.trim().parse::<usize>() {
+                Ok(required) => (Some(required), arguments),
+                Err(_e) => {
+                    return Err(format!(
+                        "invalid number of required members [{}] in group [{}]",
+                        required.trim(),
+                        entry
+                    ));
+                }
+            },
+            None => {
+                return Err(format!(
+                    "missing number of required members in group [{}]",
+                    entry
+                ));
+            }
+        },
+        _ => return Ok(None),
+    };
+    let members = split(arguments)
+        .map(|member| parse_entry(member, parser))
+        .collect::<Result<Vec<Target>, String>>()?;
+    let required = required.unwrap_or(members.len() / 2 + 1);
+    if required == 0 || required > members.len() {
+        return Err(format!(
+            "group [{}] requires {} of its {} members",
+            entry,
+            required,
+            members.len()
+        ));
+    }
+    Ok(Some(Target::Group {
+        name: entry.to_string(),
+        required,
+        members,
+    }))
+}
+
+// The default timeout of the targets is also their upper bound
+fn new_spec(
+    target: Target,
+    options: &[(String, String)],
+    timeout: u64,
+    config: &Config,
+) -> TargetSpec {
+    let mut spec = TargetSpec {
+        target,
+        success_threshold: config.success_threshold,
+        timeout,
+        interval: config.wait_sleep_interval,
+        optional: false,
+    };
+    for (key, value) in options {
+        let valid = match key.as_str() {
+            SUCCESS_THRESHOLD_OPTION => match value.parse::<u64>() {
+                Ok(threshold) if threshold > 0 => {
+                    spec.success_threshold = threshold;
+                    true
+                }
+                _ => false,
+            },
+            TIMEOUT_OPTION => match to_units(value) {
+                Some(target_timeout) => {
+                    spec.timeout = target_timeout.min(timeout);
+                    true
+                }
+                None => false,
+            },
+            INTERVAL_OPTION => match to_units(value) {
+                Some(interval) => {
+                    spec.interval = interval;
+                    true
+                }
+                None => false,
+            },
+            OPTIONAL_OPTION => match value.to_lowercase().as_str() {
+                "" | "true" => {
+                    spec.optional = true;
+                    true
+                }
+                "false" => {
+                    spec.optional = false;
+                    true
+                }
+                _ => false,
+            },
+            _ => true,
+        };
+        if !valid {
+            warn!(
+                "Invalid value [{}] of option [{}] for {}. The option is ignored.",
+                value, key, spec.target
+            );
+        }
+    }
+    spec
+}
+
+// Durations are expressed in seconds, optionally with a `s`, `m` or `h` unit suffix
+fn to_units(duration: &str) -> Option<u64> {
+    let duration = duration.trim();
+    let (number, multiplier) = match duration.char_indices().last() {
+        Some((index, 's')) => (&duration[..index], 1),
+        Some((index, 'm')) => (&duration[..index], 60),
+        Some((index, 'h')) => (&duration[..index], 60 * 60),
+        _ => (duration, 1),
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+// Options are appended to a target entry in a URL query string fashion (e.g. `db:5432?timeout=10&interval=2`).
+// A suffix with unknown options is an error, that lists like the check commands can ignore
+// so that entries like `curl http://host/health?verbose=true` are not altered.
+fn split_options(entry: &str) -> Result<(String, Vec<(String, String)>), String> {
+    let Some((target, query)) = entry.rsplit_once(OPTIONS_SEPARATOR) else {
+        return Ok((entry.to_string(), vec![]));
+    };
+    let options: Vec<(String, String)> = query
+        .split('&')
+        .map(|option| match option.split_once('=') {
+            Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+            None => (option.trim().to_string(), "".to_string()),
+        })
+        .collect();
+    let unknown: Vec<&str> = options
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .filter(|key| !OPTIONS.contains(key))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "unknown options [{}] of [{}], valid options are [{}]",
+            unknown.join(", "),
+            entry,
+            OPTIONS.join(", ")
+        ));
+    }
+    Ok((target.trim().to_string(), options))
+}
+
+// Splits a comma separated list, ignoring the commas between the parentheses of a group
+fn split(list: &str) -> impl Iterator<Item = String> + '_ {
+    let mut depth = 0;
+    list.split(move |c: char| match c {
+        '(' => {
+            depth += 1;
+            false
+        }
+        ')' => {
+            depth -= 1;
+            false
+        }
+        ',' => depth <= 0,
+        _ => false,
+    })
+    .map(|entry| entry.trim())
+    .filter(|entry| !entry.is_empty())
+    .map(|entry| entry.to_string())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_build_targets_from_config() {
+        let config = Config {
+            hosts: " localhost:1234, ,db:5432, docker://postgres, k8s://job/default/migrations "
+                .to_string(),
+            paths: "/tmp/a".to_string(),
+            processes: "nginx, pidfile: /run/app.pid".to_string(),
+            check_commands: "pg_isready -h db, ls".to_string(),
+            ..Default::default()
+        };
+
+        let targets: Vec<String> = from_config(&config)
+            .unwrap()
+            .iter()
+            .map(|target| target.to_string())
+            .collect();
+
+        assert_eq!(
+            vec![
+                "host [localhost:1234]",
+                "host [db:5432]",
+                "docker [postgres]",
+                "k8s [job/default/migrations]",
+                "path [/tmp/a]",
+                "process [nginx]",
+                "pidfile [/run/app.pid]",
+                "command [pg_isready -h db]",
+                "command [ls]",
+            ],
+            targets
+        );
+    }
+
+    #[test]
+    fn should_return_no_targets_for_an_empty_config() {
+        let config = Config {
+            hosts: "  ".to_string(),
+            ..Default::default()
+        };
+        assert!(from_config(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_fail_if_a_check_command_is_invalid() {
+        let config = Config {
+            check_commands: "ls, intentionally 'invalid".to_string(),
+            ..Default::default()
+        };
+        assert!(from_config(&config).is_err());
+    }
+
+    #[test]
+    fn should_check_the_command_exit_code() {
+        let config = Config {
+            check_commands: "true, false, sh -c 'exit 3'".to_string(),
+            ..Default::default()
+        };
+        let targets: Vec<Target> = from_config(&config)
+            .unwrap()
+            .into_iter()
+            .map(|spec| spec.target)
+            .collect();
+        assert!(targets[0].is_available(&config));
+        assert!(!targets[1].is_available(&config));
+        assert!(!targets[2].is_available(&config));
+
+        let config = Config {
+            check_command_exit_code: 3,
+            ..config
+        };
+        assert!(!targets[0].is_available(&config));
+        assert!(targets[2].is_available(&config));
+    }
+
+    #[test]
+    fn should_return_the_reason_of_a_failure() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let config = Config {
+            hosts: format!("127.0.0.1:{}, fsdfsdfwerwer.invalid:80", port),
+            paths: "./target/fsdfwerwer".to_string(),
+            check_commands: "sh -c 'echo first; echo unauthorized; exit 2'".to_string(),
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+
+        assert_eq!(
+            Err("connection refused".to_string()),
+            specs[0].target.check(&config)
+        );
+        assert!(
+            specs[1]
+                .target
+                .check(&config)
+                .unwrap_err()
+                .starts_with("DNS failure: ")
+        );
+        assert_eq!(
+            Err("path missing".to_string()),
+            specs[2].target.check(&config)
+        );
+        assert_eq!(
+            Err("exited with code 2: unauthorized".to_string()),
+            specs[3].target.check(&config)
+        );
+    }
+
+    #[test]
+    fn should_truncate_long_reasons() {
+        assert_eq!("short", truncate("short"));
+        assert_eq!(
+            format!("{}...", "a".repeat(MAX_REASON_LENGTH)),
+            truncate(&"a".repeat(MAX_REASON_LENGTH + 1))
+        );
+    }
+
+    #[test]
+    fn should_use_the_global_success_threshold() {
+        let config = Config {
+            hosts: "db:5432".to_string(),
+            success_threshold: 4,
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+        assert_eq!(4, specs[0].success_threshold);
+    }
+
+    #[test]
+    fn should_read_the_success_threshold_of_a_target() {
+        let config = Config {
+            hosts: "db:5432?success_threshold=3, cache:6379 ? success_threshold = 0".to_string(),
+            paths: "/tmp/a?success_threshold=2".to_string(),
+            check_commands: "curl http://localhost/health?full=true&success_threshold=5"
+                .to_string(),
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+
+        assert_eq!("host [db:5432]", specs[0].to_string());
+        assert_eq!(3, specs[0].success_threshold);
+        assert_eq!("host [cache:6379]", specs[1].to_string());
+        assert_eq!(1, specs[1].success_threshold);
+        assert_eq!("path [/tmp/a]", specs[2].to_string());
+        assert_eq!(2, specs[2].success_threshold);
+        assert_eq!(
+            "command [curl http://localhost/health?full=true&success_threshold=5]",
+            specs[3].to_string()
+        );
+        assert_eq!(1, specs[3].success_threshold);
+    }
+
+    #[test]
+    fn should_read_the_timeout_and_interval_of_a_target() {
+        let config = Config {
+            hosts: "db:5432?timeout=120s&interval=2, cache:6379?timeout=10".to_string(),
+            paths: "/tmp/a?interval=1m, /tmp/b?timeout=abc&interval=".to_string(),
+            global_timeout: 300,
+            wait_sleep_interval: 3,
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+
+        assert_eq!("host [db:5432]", specs[0].to_string());
+        assert_eq!(120, specs[0].timeout);
+        assert_eq!(2, specs[0].interval);
+        assert_eq!("host [cache:6379]", specs[1].to_string());
+        assert_eq!(10, specs[1].timeout);
+        assert_eq!(3, specs[1].interval);
+        assert_eq!("path [/tmp/a]", specs[2].to_string());
+        assert_eq!(300, specs[2].timeout);
+        assert_eq!(60, specs[2].interval);
+        assert_eq!("path [/tmp/b]", specs[3].to_string());
+        assert_eq!(300, specs[3].timeout);
+        assert_eq!(3, specs[3].interval);
+    }
+
+    #[test]
+    fn target_timeout_should_be_bounded_by_the_global_timeout() {
+        let config = Config {
+            hosts: "db:5432?timeout=1h".to_string(),
+            global_timeout: 30,
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+        assert_eq!(30, specs[0].timeout);
+    }
+
+    #[test]
+    fn should_convert_durations_to_units() {
+        assert_eq!(Some(10), to_units("10"));
+        assert_eq!(Some(10), to_units(" 10s "));
+        assert_eq!(Some(120), to_units("2m"));
+        assert_eq!(Some(7200), to_units("2h"));
+        assert_eq!(None, to_units("2d"));
+        assert_eq!(None, to_units("s"));
+        assert_eq!(None, to_units(""));
+        assert_eq!(None, to_units("-1"));
+    }
+
+    #[test]
+    fn should_read_the_optional_flag_of_a_target() {
+        let config = Config {
+            hosts: "db:5432, metrics:9090?optional, tracing:4317?timeout=5&optional=true"
+                .to_string(),
+            paths: "/tmp/a?optional=false, /tmp/b?optional=maybe".to_string(),
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+
+        assert!(!specs[0].optional);
+        assert_eq!("host [metrics:9090]", specs[1].to_string());
+        assert!(specs[1].optional);
+        assert_eq!("host [tracing:4317]", specs[2].to_string());
+        assert!(specs[2].optional);
+        assert_eq!(5, specs[2].timeout);
+        assert!(!specs[3].optional);
+        assert!(!specs[4].optional);
+    }
+
+    #[test]
+    fn should_reject_unknown_options_except_for_check_commands() {
+        let config = Config {
+            hosts: "db:5432?timeot=5".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Err("unknown options [timeot] of [db:5432?timeot=5], valid options are [success_threshold, timeout, interval, optional]".to_string()),
+            from_config(&config).map(|specs| specs.len())
+        );
+        let config = Config {
+            paths: "/tmp/a?optional&verbose".to_string(),
+            ..Default::default()
+        };
+        assert!(from_config(&config).is_err());
+
+        let config = Config {
+            check_commands: "curl http://localhost/health?verbose=true".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            "command [curl http://localhost/health?verbose=true]",
+            from_config(&config).unwrap()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn should_split_lists_with_groups() {
+        let entries: Vec<String> =
+            split("a:1, any(b:1, c:1)?timeout=5, atleast(2; d:1,e:1, f:1), g:1").collect();
+        assert_eq!(
+            vec![
+                "a:1",
+                "any(b:1, c:1)?timeout=5",
+                "atleast(2; d:1,e:1, f:1)",
+                "g:1"
+            ],
+            entries
+        );
+    }
+
+    #[test]
+    fn should_parse_groups() {
+        let config = Config {
+            hosts: "any(a:9042, b:9042, c:9042)?timeout=10, QUORUM(d:1, e:1, f:1, g:1), atleast(2; h:1, i:1, j:1)".to_string(),
+            processes: "any(nginx, pidfile:/run/nginx.pid)".to_string(),
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+        assert_eq!(4, specs.len());
+
+        assert_eq!("group [any(a:9042, b:9042, c:9042)]", specs[0].to_string());
+        assert_eq!(10, specs[0].timeout);
+        match &specs[0].target {
+            Target::Group {
+                required, members, ..
+            } => {
+                assert_eq!(1, *required);
+                let members: Vec<String> = members.iter().map(|m| m.to_string()).collect();
+                assert_eq!(
+                    vec!["host [a:9042]", "host [b:9042]", "host [c:9042]"],
+                    members
+                );
+            }
+            _ => panic!("a group was expected"),
+        }
+        match &specs[1].target {
+            Target::Group {
+                required, members, ..
+            } => {
+                assert_eq!(3, *required);
+                assert_eq!(4, members.len());
+            }
+            _ => panic!("a group was expected"),
+        }
+        match &specs[2].target {
+            Target::Group {
+                required, members, ..
+            } => {
+                assert_eq!(2, *required);
+                assert_eq!(3, members.len());
+            }
+            _ => panic!("a group was expected"),
+        }
+        match &specs[3].target {
+            Target::Group { members, .. } => {
+                let members: Vec<String> = members.iter().map(|m| m.to_string()).collect();
+                assert_eq!(vec!["process [nginx]", "pidfile [/run/nginx.pid]"], members);
+            }
+            _ => panic!("a group was expected"),
+        }
+    }
+
+    #[test]
+    fn should_fail_if_a_group_is_invalid() {
+        for hosts in &[
+            "atleast(a:1, b:1)",
+            "atleast(x; a:1, b:1)",
+            "atleast(3; a:1, b:1)",
+            "atleast(0; a:1, b:1)",
+            "any()",
+        ] {
+            let config = Config {
+                hosts: hosts.to_string(),
+                ..Default::default()
+            };
+            assert!(from_config(&config).is_err(), "{} should be invalid", hosts);
+        }
+    }
+
+    #[test]
+    fn group_should_be_available_when_enough_members_are() {
+        let config = Config {
+            paths: "any(./target/fsdfwerwer, ./target), quorum(./target/sdfsdfwer, ./target, ./src), atleast(2; ./target/sdfsdfwer, ./target, ./target/sdfsdfwersd)".to_string(),
+            ..Default::default()
+        };
+        let specs = from_config(&config).unwrap();
+        assert!(specs[0].target.is_available(&config));
+        assert!(specs[1].target.is_available(&config));
+        assert!(!specs[2].target.is_available(&config));
+    }
+
+    #[test]
+    fn should_build_targets_from_a_stage() {
+        let config = Config {
+            hosts: "db:5432".to_string(),
+            global_timeout: 100,
+            ..Default::default()
+        };
+        let stage = Stage {
+            name: "vault".to_string(),
+            hosts: "vault:8200, consul:8500?timeout=50".to_string(),
+            paths: "/tmp/a?timeout=5".to_string(),
+            timeout: Some(20),
+            ..Default::default()
+        };
+        let specs = from_stage(&stage, &config).unwrap();
+
+        assert_eq!(3, specs.len());
+        assert_eq!("host [vault:8200]", specs[0].to_string());
+        assert_eq!(20, specs[0].timeout);
+        assert_eq!("host [consul:8500]", specs[1].to_string());
+        assert_eq!(20, specs[1].timeout);
+        assert_eq!("path [/tmp/a]", specs[2].to_string());
+        assert_eq!(5, specs[2].timeout);
+    }
+
+    #[test]
+    fn stage_timeout_should_be_bounded_by_the_global_timeout() {
+        let config = Config {
+            global_timeout: 100,
+            ..Default::default()
+        };
+        let stage = Stage {
+            hosts: "vault:8200".to_string(),
+            timeout: Some(200),
+            ..Default::default()
+        };
+        assert_eq!(100, from_stage(&stage, &config).unwrap()[0].timeout);
+
+        let stage = Stage {
+            timeout: None,
+            ..stage
+        };
+        assert_eq!(100, from_stage(&stage, &config).unwrap()[0].timeout);
+    }
+}
@@ -0,0 +1,360 @@
+use crate::sleeper::Sleeper;
+use crate::status::Status;
+use crate::target::TargetSpec;
+use crate::{Config, exit_code, hooks, signal};
+use log::*;
+use std::fmt;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, ExitStatus};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// The signals received by the wait process are forwarded with at most this delay
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Log,
+    Signal(i32),
+    Terminate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Watch {
+    pub action: Action,
+    pub interval: u64,
+    pub failure_threshold: u64,
+    pub kill_timeout: u64,
+}
+
+impl fmt::Display for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.action {
+            Action::Log => write!(f, "log")?,
+            Action::Signal(signal) => write!(f, "signal {}", signal)?,
+            Action::Terminate => write!(f, "terminate")?,
+        }
+        write!(
+            f,
+            " after {} failed checks, checks every {} seconds, kill timeout {} seconds",
+            self.failure_threshold, self.interval, self.kill_timeout
+        )
+    }
+}
+
+pub fn action_from_str(action: &str, signal: i32) -> Option<Action> {
+    match action.trim().to_lowercase().as_str() {
+        "log" => Some(Action::Log),
+        "signal" => Some(Action::Signal(signal)),
+        "terminate" => Some(Action::Terminate),
+        _ => None,
+    }
+}
+
+// Checks the required targets every `watch.interval` units until the child exits
+// and runs the action when one of them fails `watch.failure_threshold` consecutive checks.
+// A command still running `watch.kill_timeout` units after being terminated is killed.
+// The targets are in the same order they were added to the status.
+// Returns the exit code of the child.
+pub fn supervise(
+    sleep: &mut dyn Sleeper,
+    config: &Config,
+    watch: &Watch,
+    status: &Status,
+    targets: &[TargetSpec],
+    child: &mut Child,
+) -> i32 {
+    // A check can block up to its connection or command timeout, so the checks run in their
+    // own thread while this one keeps forwarding the signals and returns as soon as the child exits
+    let check_config = Arc::new(config.clone());
+    let required: Arc<Vec<TargetSpec>> = Arc::new(
+        targets
+            .iter()
+            .filter(|spec| !spec.optional)
+            .cloned()
+            .collect(),
+    );
+    let mut checks: Option<JoinHandle<Vec<Result<(), String>>>> = None;
+    let mut failures = vec![0; targets.len()];
+    let mut next_check_at = sleep.elapsed_units().saturating_add(watch.interval);
+    let mut terminating = false;
+    let mut kill_at: Option<u64> = None;
+    loop {
+        for signal in signal::take_received() {
+            info!("Forwarding signal {} to the command", signal);
+            send_signal(child, signal);
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                info!("The command exited with {}", status);
+                return exit_code(status);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Failed to check the status of the command: {}", e);
+                return 1;
+            }
+        }
+        if kill_at.is_some_and(|kill_at| sleep.elapsed(kill_at)) {
+            warn!(
+                "The command is still running {} seconds after being terminated. Killing it",
+                watch.kill_timeout
+            );
+            send_signal(child, libc::SIGKILL);
+            kill_at = None;
+        }
+        if !terminating && checks.is_none() && sleep.elapsed(next_check_at) {
+            let (config, required) = (check_config.clone(), required.clone());
+            checks = Some(thread::spawn(move || {
+                required
+                    .iter()
+                    .map(|spec| spec.target.check(&config))
+                    .collect()
+            }));
+        }
+        if !checks.as_ref().is_some_and(|checks| checks.is_finished()) {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        let results = checks
+            .take()
+            .and_then(|checks| checks.join().ok())
+            .unwrap_or_default();
+        for ((index, (spec, failures)), result) in targets
+            .iter()
+            .zip(failures.iter_mut())
+            .enumerate()
+            .filter(|(_, (spec, _))| !spec.optional)
+            .zip(results)
+        {
+            if terminating {
+                break;
+            }
+            status.checked(index, &result, result.is_ok());
+            if result.is_ok() {
+                if *failures >= watch.failure_threshold {
+                    info!("{} is available again", spec);
+                    config.hooks.run(
+                        &hooks::Event::for_target(hooks::AVAILABLE, spec, ""),
+                        sleep.elapsed_units(),
+                    );
+                }
+                *failures = 0;
+                continue;
+            }
+            *failures += 1;
+            debug!(
+                "{} not available ({}/{} consecutive failed checks)",
+                spec, failures, watch.failure_threshold
+            );
+            if *failures != watch.failure_threshold {
+                continue;
+            }
+            let error = result.err().unwrap_or_default();
+            error!(
+                "{} is not available after {} consecutive checks: {}",
+                spec, watch.failure_threshold, error
+            );
+            config.hooks.run(
+                &hooks::Event::for_target(hooks::UNAVAILABLE, spec, &error),
+                sleep.elapsed_units(),
+            );
+            match watch.action {
+                Action::Log => {}
+                Action::Signal(signal) => {
+                    info!("Sending signal {} to the command", signal);
+                    send_signal(child, signal);
+                    *failures = 0;
+                }
+                Action::Terminate => {
+                    info!("Terminating the command");
+                    send_signal(child, libc::SIGTERM);
+                    terminating = true;
+                    kill_at = Some(sleep.elapsed_units().saturating_add(watch.kill_timeout));
+                }
+            }
+        }
+        next_check_at = sleep.elapsed_units().saturating_add(watch.interval);
+    }
+}
+
+fn send_signal(child: &Child, signal: i32) {
+    // SAFETY: the child has not been reaped yet, so its pid cannot have been reused
+    if unsafe { libc::kill(child.id() as libc::pid_t, signal) } != 0 {
+        warn!(
+            "Failed to send signal {} to the command: {}",
+            signal,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+// Follows the shell convention for processes killed by a signal
+fn exit_code(status: ExitStatus) -> i32 {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code,
+        (None, Some(signal)) => exit_code::SIGNAL_BASE + signal,
+        (None, None) => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::sleeper::MillisSleeper;
+    use crate::target;
+    use std::process::Command;
+
+    fn targets(paths: &str) -> (Config, Vec<TargetSpec>) {
+        let config = Config {
+            paths: paths.to_string(),
+            ..Default::default()
+        };
+        let targets = target::from_config(&config).unwrap();
+        (config, targets)
+    }
+
+    #[test]
+    fn should_parse_the_action() {
+        assert_eq!(None, action_from_str("", libc::SIGHUP));
+        assert_eq!(None, action_from_str("none", libc::SIGHUP));
+        assert_eq!(Some(Action::Log), action_from_str(" LOG ", libc::SIGHUP));
+        assert_eq!(
+            Some(Action::Signal(libc::SIGUSR1)),
+            action_from_str("signal", libc::SIGUSR1)
+        );
+        assert_eq!(
+            Some(Action::Terminate),
+            action_from_str("terminate", libc::SIGHUP)
+        );
+    }
+
+    #[test]
+    fn should_describe_the_watch() {
+        let watch = Watch {
+            action: Action::Signal(libc::SIGHUP),
+            interval: 10,
+            failure_threshold: 3,
+            kill_timeout: 5,
+        };
+        assert_eq!(
+            "signal 1 after 3 failed checks, checks every 10 seconds, kill timeout 5 seconds",
+            watch.to_string()
+        );
+    }
+
+    #[test]
+    fn should_return_the_exit_code_of_the_command() {
+        let (config, targets) = targets("./target/fsdfwerwer");
+        let watch = Watch {
+            action: Action::Terminate,
+            interval: 10,
+            failure_threshold: 1000,
+            kill_timeout: 1000,
+        };
+        let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+        let code = supervise(
+            &mut MillisSleeper::default(),
+            &config,
+            &watch,
+            &Status::default(),
+            &targets,
+            &mut child,
+        );
+        assert_eq!(3, code);
+    }
+
+    #[test]
+    fn should_terminate_the_command_when_a_target_is_not_available() {
+        let (config, targets) = targets("./target, ./target/fsdfwerwer");
+        let watch = Watch {
+            action: Action::Terminate,
+            interval: 10,
+            failure_threshold: 3,
+            kill_timeout: 1000,
+        };
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        let start = std::time::Instant::now();
+        let code = supervise(
+            &mut MillisSleeper::default(),
+            &config,
+            &watch,
+            &Status::default(),
+            &targets,
+            &mut child,
+        );
+        assert_eq!(128 + libc::SIGTERM, code);
+        assert!(start.elapsed().as_millis() >= 30);
+        assert!(start.elapsed().as_secs() < 5);
+    }
+
+    #[test]
+    fn should_kill_the_command_if_it_ignores_the_termination() {
+        let (config, targets) = targets("./target/fsdfwerwer");
+        let watch = Watch {
+            action: Action::Terminate,
+            interval: 20,
+            failure_threshold: 3,
+            kill_timeout: 100,
+        };
+        let mut child = Command::new("sh")
+            .args(["-c", "trap '' TERM; exec sleep 10"])
+            .spawn()
+            .unwrap();
+        let start = std::time::Instant::now();
+        let code = supervise(
+            &mut MillisSleeper::default(),
+            &config,
+            &watch,
+            &Status::default(),
+            &targets,
+            &mut child,
+        );
+        assert_eq!(128 + libc::SIGKILL, code);
+        assert!(start.elapsed().as_secs() < 5);
+    }
+
+    #[test]
+    fn should_send_the_signal_when_a_target_is_not_available() {
+        let (config, targets) = targets("./target/fsdfwerwer");
+        let watch = Watch {
+            action: Action::Signal(libc::SIGUSR1),
+            interval: 10,
+            failure_threshold: 2,
+            kill_timeout: 1000,
+        };
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        let code = supervise(
+            &mut MillisSleeper::default(),
+            &config,
+            &watch,
+            &Status::default(),
+            &targets,
+            &mut child,
+        );
+        assert_eq!(128 + libc::SIGUSR1, code);
+    }
+
+    #[test]
+    fn should_only_log_when_a_target_is_not_available() {
+        let (config, targets) = targets("./target/fsdfwerwer");
+        let watch = Watch {
+            action: Action::Log,
+            interval: 10,
+            failure_threshold: 1,
+            kill_timeout: 1000,
+        };
+        let mut child = Command::new("sleep").arg("0.5").spawn().unwrap();
+        let code = supervise(
+            &mut MillisSleeper::default(),
+            &config,
+            &watch,
+            &Status::default(),
+            &targets,
+            &mut child,
+        );
+        assert_eq!(0, code);
+    }
+}
@@ -14,7 +14,8 @@ fn should_wait_for_5_seconds_before() {
         &mut sleeper,
         &new_config("", "", 1, wait_for, 0, 1, 1),
         &mut on_timeout,
-    );
+    )
+    .unwrap();
     assert!(millis_elapsed(start) >= wait_for)
 }
 
@@ -27,7 +28,8 @@ fn should_wait_for_10_seconds_after() {
         &mut sleeper,
         &new_config("", "", 1, 0, wait_for, 1, 1),
         &mut on_timeout,
-    );
+    )
+    .unwrap();
     assert!(millis_elapsed(start) >= wait_for)
 }
 
@@ -40,7 +42,8 @@ fn should_wait_before_and_after() {
         &mut sleeper,
         &new_config("", "", 1, wait_for, wait_for, 1, 1),
         &mut on_timeout,
-    );
+    )
+    .unwrap();
     assert!(millis_elapsed(start) >= (wait_for + wait_for))
 }
 
@@ -52,7 +55,8 @@ fn should_execute_without_wait() {
         &mut sleeper,
         &new_config("", "", 1, 0, 0, 1, 1),
         &mut on_timeout,
-    );
+    )
+    .unwrap();
     assert!(millis_elapsed(start) <= 5)
 }
 
@@ -64,7 +68,8 @@ fn should_sleep_the_specified_time_between_host_checks() {
         &mut sleeper,
         &new_config("198.19.255.255:1", "", 2_000, 0, 0, 10, 1),
         &mut on_timeout,
-    );
+    )
+    .unwrap();
     let elapsed = millis_elapsed(start);
     assert!(elapsed >= 2010);
     assert!(elapsed < 3000);
@@ -86,7 +91,8 @@ fn should_sleep_the_specified_time_between_path_checks() {
             1,
         ),
         &mut on_timeout,
-    );
+    )
+    .unwrap();
     let elapsed = millis_elapsed(start);
     assert!(elapsed >= 2000);
     assert!(elapsed < 3000);
@@ -112,7 +118,8 @@ fn should_exit_on_host_timeout() {
         &mut sleeper,
         &new_config(&hosts, paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     // assert that the on_timeout callback was called
     assert_eq!(1, count.get());
@@ -121,6 +128,45 @@ fn should_exit_on_host_timeout() {
     assert!(millis_elapsed(start) < timeout + wait_after);
 }
 
+#[test]
+fn should_exit_on_timeout_when_the_interval_is_longer_than_the_timeout() {
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let mut config = new_config(
+        "",
+        "./target/fsafasdfasfasfasfasfw54s664?interval=2000",
+        200,
+        0,
+        0,
+        1,
+        1,
+    );
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+    assert_eq!(1, count.get());
+    assert!(millis_elapsed(start) >= 200);
+    assert!(millis_elapsed(start) < 1000);
+
+    // The exponential backoff does not overshoot the timeout either
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+    config.paths = "./target/fsafasdfasfasfasfasfw54s664".to_string();
+    config.wait_sleep_interval = 150;
+    config.backoff.strategy = wait::backoff::Strategy::Exponential {
+        multiplier: 10.0,
+        max_interval: 5000,
+    };
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+    assert_eq!(2, count.get());
+    assert!(millis_elapsed(start) >= 200);
+    assert!(millis_elapsed(start) < 1000);
+}
+
 #[test]
 fn should_exit_on_path_timeout() {
     let timeout = 25;
@@ -141,7 +187,8 @@ fn should_exit_on_path_timeout() {
         &mut sleeper,
         &new_config(hosts, paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     // assert that the on_timeout callback was called
     assert_eq!(1, count.get());
@@ -176,7 +223,8 @@ fn should_identify_the_open_port() {
         &mut sleeper,
         &new_config(&hosts, paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(0, count.get());
 
@@ -215,7 +263,8 @@ fn should_wait_for_multiple_hosts() {
         &mut sleeper,
         &new_config(&hosts, paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(0, count.get());
 
@@ -257,7 +306,8 @@ fn should_wait_for_multiple_paths() {
         &mut sleeper,
         &new_config(hosts, &paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(0, count.get());
 
@@ -308,7 +358,8 @@ fn should_wait_for_multiple_hosts_and_paths() {
         &mut sleeper,
         &new_config(&hosts, &paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(0, count.get());
 
@@ -343,7 +394,8 @@ fn should_fail_if_not_all_hosts_are_available() {
         &mut sleeper,
         &new_config(&hosts, paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(1, count.get());
 
@@ -382,7 +434,8 @@ fn should_fail_if_not_all_paths_are_available() {
         &mut sleeper,
         &new_config(hosts, &paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(1, count.get());
 
@@ -416,7 +469,8 @@ fn should_fail_if_hosts_are_available_but_paths_are_not() {
         &mut sleeper,
         &new_config(&hosts, paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(1, count.get());
 
@@ -446,7 +500,8 @@ fn should_fail_if_paths_are_available_but_hosts_are_not() {
         &mut sleeper,
         &new_config(&hosts, paths, timeout, wait_before, wait_after, 1, 1),
         &mut fun,
-    );
+    )
+    .unwrap();
 
     assert_eq!(1, count.get());
 
@@ -454,6 +509,674 @@ fn should_fail_if_paths_are_available_but_hosts_are_not() {
     assert!(millis_elapsed(start) < timeout + wait_before + wait_after);
 }
 
+#[test]
+fn should_wait_for_a_pidfile_of_a_running_process() {
+    let timeout = 500;
+    let path = format!("./target/{}.pid", rand::random::<u64>());
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let pidfile = path.clone();
+    thread::spawn(move || {
+        thread::sleep(time::Duration::from_millis(100));
+        std::fs::write(&pidfile, std::process::id().to_string()).unwrap();
+    });
+
+    let mut config = new_config("", "", timeout, 0, 0, 1, 1);
+    config.processes = format!("pidfile:{}", path);
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(0, count.get());
+
+    assert!(millis_elapsed(start) >= 100);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_fail_if_the_process_is_not_running() {
+    let timeout = 100;
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let mut config = new_config("", "", timeout, 0, 0, 1, 1);
+    config.processes = "fsdfsdgsdfgwerwerwerwer".to_string();
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(1, count.get());
+
+    assert!(millis_elapsed(start) >= timeout);
+}
+
+#[test]
+fn should_wait_for_a_check_command_to_succeed() {
+    let timeout = 1000;
+    let path = format!("./target/{}", rand::random::<u64>());
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let file = path.clone();
+    thread::spawn(move || {
+        thread::sleep(time::Duration::from_millis(100));
+        File::create(&file).unwrap();
+    });
+
+    let mut config = new_config("", "", timeout, 0, 0, 10, 1);
+    config.check_commands = format!("test -e {}", path);
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(0, count.get());
+
+    assert!(millis_elapsed(start) >= 100);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_fail_if_the_check_command_does_not_succeed() {
+    let timeout = 100;
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let mut config = new_config("", "", timeout, 0, 0, 1, 1);
+    config.check_commands = "sh -c 'exit 1'".to_string();
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(1, count.get());
+
+    assert!(millis_elapsed(start) >= timeout);
+}
+
+#[test]
+fn should_wait_for_consecutive_successful_checks() {
+    let timeout = 1000;
+    let sleep = 50;
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let mut config = new_config("", "./target", timeout, 0, 0, sleep, 1);
+    config.success_threshold = 3;
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(0, count.get());
+
+    assert!(millis_elapsed(start) >= 2 * sleep);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_reset_the_successful_checks_on_failure() {
+    let timeout = 300;
+    let path = format!("./target/{}", rand::random::<u64>());
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let file = path.clone();
+    thread::spawn(move || {
+        File::create(&file).unwrap();
+        thread::sleep(time::Duration::from_millis(50));
+        std::fs::remove_file(&file).unwrap();
+    });
+    thread::sleep(time::Duration::from_millis(10));
+
+    let paths = format!("{}?success_threshold=5", path);
+    wait::wait(
+        &mut sleeper,
+        &new_config("", &paths, timeout, 0, 0, 20, 1),
+        &mut fun,
+    )
+    .unwrap();
+
+    assert_eq!(1, count.get());
+
+    assert!(millis_elapsed(start) >= timeout);
+}
+
+#[test]
+fn should_increase_the_sleep_time_between_checks() {
+    let timeout = 1000;
+    let path = format!("./target/{}", rand::random::<u64>());
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let file = path.clone();
+    thread::spawn(move || {
+        thread::sleep(time::Duration::from_millis(15));
+        File::create(&file).unwrap();
+    });
+
+    // checks are performed after 0, 10, 30 and 70 millis
+    let mut config = new_config("", &path, timeout, 0, 0, 10, 1);
+    config.backoff = wait::backoff::Backoff {
+        strategy: wait::backoff::Strategy::Exponential {
+            multiplier: 2.0,
+            max_interval: 100,
+        },
+        jitter: wait::backoff::Jitter::None,
+    };
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(0, count.get());
+
+    assert!(millis_elapsed(start) >= 30);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_exit_on_target_timeout() {
+    let timeout = 2000;
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let paths = "./target, ./target/fsdfsdfsdfsdfewrwerwerwer?timeout=50";
+    wait::wait(
+        &mut sleeper,
+        &new_config("", paths, timeout, 0, 0, 1, 1),
+        &mut fun,
+    )
+    .unwrap();
+
+    assert_eq!(1, count.get());
+
+    assert!(millis_elapsed(start) >= 50);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_check_all_targets_while_waiting() {
+    let timeout = 1000;
+    let path_1 = format!("./target/{}", rand::random::<u64>());
+    let path_2 = format!("./target/{}", rand::random::<u64>());
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    // The second path is available only for a short time while the first one is still missing
+    let (file_1, file_2) = (path_1.clone(), path_2.clone());
+    thread::spawn(move || {
+        File::create(&file_2).unwrap();
+        thread::sleep(time::Duration::from_millis(100));
+        std::fs::remove_file(&file_2).unwrap();
+        File::create(&file_1).unwrap();
+    });
+    thread::sleep(time::Duration::from_millis(10));
+
+    let paths = format!("{}, {}?timeout=200&interval=5", path_1, path_2);
+    wait::wait(
+        &mut sleeper,
+        &new_config("", &paths, timeout, 0, 0, 20, 1),
+        &mut fun,
+    )
+    .unwrap();
+
+    assert_eq!(0, count.get());
+
+    assert!(millis_elapsed(start) >= 100);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_skip_optional_targets_on_timeout() {
+    let timeout = 1000;
+    let wait_after = 30;
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let hosts = format!("127.0.0.1:{}?optional&timeout=50", free_port());
+    let paths = "./target, ./target/sdfsdfwerwerwerwerwer?optional";
+    let mut config = new_config(&hosts, paths, timeout, 0, wait_after, 1, 1);
+    config.global_timeout = 100;
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(0, count.get());
+
+    assert!(millis_elapsed(start) >= 100 + wait_after);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_fail_if_required_targets_are_not_available_with_optional_ones() {
+    let timeout = 100;
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let paths = "./target?optional, ./target/sdfsdfwerwerwerwerwer";
+    wait::wait(
+        &mut sleeper,
+        &new_config("", paths, timeout, 0, 0, 1, 1),
+        &mut fun,
+    )
+    .unwrap();
+
+    assert_eq!(1, count.get());
+
+    assert!(millis_elapsed(start) >= timeout);
+}
+
+#[test]
+fn should_wait_for_any_host_of_a_group() {
+    let timeout = 500;
+
+    let tcp_listener = new_tcp_listener();
+    let hosts = format!(
+        "any(127.0.0.1:{}, {}, 127.0.0.1:{})",
+        free_port(),
+        tcp_listener.local_addr().unwrap(),
+        free_port()
+    );
+    listen_async(tcp_listener);
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    wait::wait(
+        &mut sleeper,
+        &new_config(&hosts, "", timeout, 0, 0, 1, 1),
+        &mut fun,
+    )
+    .unwrap();
+
+    assert_eq!(0, count.get());
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_fail_if_a_group_has_not_enough_hosts_available() {
+    let timeout = 100;
+
+    let tcp_listener = new_tcp_listener();
+    let hosts = format!(
+        "quorum(127.0.0.1:{}, {}, 127.0.0.1:{})",
+        free_port(),
+        tcp_listener.local_addr().unwrap(),
+        free_port()
+    );
+    listen_async(tcp_listener);
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    wait::wait(
+        &mut sleeper,
+        &new_config(&hosts, "", timeout, 0, 0, 1, 1),
+        &mut fun,
+    )
+    .unwrap();
+
+    assert_eq!(1, count.get());
+    assert!(millis_elapsed(start) >= timeout);
+}
+
+#[test]
+fn should_wait_for_stages_in_order() {
+    let timeout = 1000;
+    let path_1 = format!("./target/{}", rand::random::<u64>());
+    let path_2 = format!("./target/{}", rand::random::<u64>());
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let (file_1, file_2) = (path_1.clone(), path_2.clone());
+    thread::spawn(move || {
+        thread::sleep(time::Duration::from_millis(100));
+        File::create(&file_1).unwrap();
+        thread::sleep(time::Duration::from_millis(50));
+        File::create(&file_2).unwrap();
+    });
+
+    // The timeout of the second stage starts once the first stage is completed
+    let mut config = new_config("", "./target", timeout, 0, 0, 1, 1);
+    config.stages = vec![
+        wait::Stage {
+            name: "first".to_string(),
+            paths: path_1,
+            wait_after: 10,
+            ..Default::default()
+        },
+        wait::Stage {
+            name: "second".to_string(),
+            paths: path_2,
+            timeout: Some(100),
+            ..Default::default()
+        },
+    ];
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(0, count.get());
+
+    assert!(millis_elapsed(start) >= 150);
+    assert!(millis_elapsed(start) < timeout);
+}
+
+#[test]
+fn should_exit_on_stage_timeout() {
+    let timeout = 1000;
+    let wait_after = 300;
+
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let mut config = new_config("", "", timeout, 0, wait_after, 1, 1);
+    config.stages = vec![
+        wait::Stage {
+            name: "first".to_string(),
+            paths: "./target".to_string(),
+            wait_before: 20,
+            ..Default::default()
+        },
+        wait::Stage {
+            name: "second".to_string(),
+            paths: "./target/fsdfsdfwerwerwerwerwe".to_string(),
+            timeout: Some(50),
+            ..Default::default()
+        },
+    ];
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(1, count.get());
+
+    assert!(millis_elapsed(start) >= 70);
+    assert!(millis_elapsed(start) < wait_after);
+}
+
+#[test]
+fn should_not_wait_in_explain_mode() {
+    let start = Instant::now();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let mut config = new_config("", "./target/fsdfsdfwerwerwerwerwe", 1000, 500, 500, 1, 1);
+    config.explain = Some(wait::explain::Format::Json);
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert_eq!(0, count.get());
+    assert!(millis_elapsed(start) < 500);
+}
+
+// Runs the executable and returns its exit code, if it exits within 5 seconds
+fn run_executable(args: &[&str], vars: &[(&str, &str)]) -> Option<i32> {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_wait"))
+        .args(args)
+        .envs(vars.iter().copied())
+        .env("WAIT_LOGGER_LEVEL", "off")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break Some(status);
+        }
+        if millis_elapsed(start) > 5000 {
+            child.kill().unwrap();
+            break None;
+        }
+        thread::sleep(time::Duration::from_millis(10));
+    };
+    status.and_then(|status| status.code())
+}
+
+#[test]
+fn should_not_serve_in_explain_mode() {
+    assert_eq!(
+        Some(0),
+        run_executable(
+            &["serve", "--explain", "--barrier-addr", "127.0.0.1:0"],
+            &[]
+        )
+    );
+}
+
+#[test]
+fn should_report_the_stages_not_started_on_timeout() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_wait"))
+        .env("WAIT_PATHS", "./target/fsdfsdfwerwerwerwerwe")
+        .env("WAIT_TIMEOUT", "1")
+        .env("WAIT_STAGE_1_PATHS", "./target")
+        .env("WAIT_LOGGER_LEVEL", "error")
+        .output()
+        .unwrap();
+    let report = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(Some(1), output.status.code());
+    assert!(report.contains("path [./target/fsdfsdfwerwerwerwerwe]: timed out"));
+    assert!(report.contains("path [./target]: not started, 0 attempts"));
+}
+
+#[test]
+fn should_forward_the_signals_while_a_watch_check_is_running() {
+    // Available for the wait, then blocked in every check of the watch
+    let flag = format!("./target/{}.checked", rand::random::<u64>());
+    let check = format!(
+        "sh -c 'if [ -e {} ]; then sleep 5; else touch {}; fi'",
+        flag, flag
+    );
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_wait"))
+        .env("WAIT_CHECK_COMMANDS", &check)
+        .env("WAIT_COMMAND", "sleep 30")
+        .env("WAIT_WATCH", "log")
+        .env("WAIT_WATCH_INTERVAL", "1")
+        .env("WAIT_LOGGER_LEVEL", "off")
+        .spawn()
+        .unwrap();
+    thread::sleep(time::Duration::from_millis(1500));
+    // SAFETY: the child has not been reaped yet, so its pid cannot have been reused
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break Some(status);
+        }
+        if start.elapsed().as_secs() > 10 {
+            child.kill().unwrap();
+            break None;
+        }
+        thread::sleep(time::Duration::from_millis(10));
+    };
+    assert_eq!(
+        Some(128 + libc::SIGTERM),
+        status.and_then(|status| status.code())
+    );
+    assert!(millis_elapsed(start) < 1000);
+}
+
+#[test]
+fn should_exit_with_the_invalid_configuration_code() {
+    assert_eq!(
+        Some(2),
+        run_executable(&[], &[("WAIT_TIMEOUT_EXIT_CODE", "300")])
+    );
+    assert_eq!(
+        Some(2),
+        run_executable(&[], &[("WAIT_HOSTS", "atleast(3; db:5432)")])
+    );
+
+    // Docker reserves the exit code 2 of a healthcheck
+    assert_eq!(
+        Some(1),
+        run_executable(&["check"], &[("WAIT_TIMEOUT_EXIT_CODE", "300")])
+    );
+
+    // The logger level is also set by the test, so its file variable conflicts with it
+    assert_eq!(
+        Some(2),
+        run_executable(&[], &[("WAIT_LOGGER_LEVEL_FILE", "./Cargo.toml")])
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    assert_eq!(
+        Some(2),
+        run_executable(&[], &[("WAIT_STATUS_ADDR", addr.as_str())])
+    );
+}
+
+#[test]
+fn should_check_every_target_once() {
+    let tcp_listener = new_tcp_listener();
+    let host = tcp_listener.local_addr().unwrap().to_string();
+    let start = Instant::now();
+
+    let mut config = new_config(&host, "./target", 1000, 500, 500, 1, 1);
+    config.stages = vec![wait::Stage {
+        paths: "./target/fsdfsdfwerwerwerwerwe?optional".to_string(),
+        ..Default::default()
+    }];
+    let (healthy, status) = wait::check(&config).unwrap();
+    assert!(healthy);
+    assert_eq!(
+        "healthy: 2/3 targets available, optional not available: [path [./target/fsdfsdfwerwerwerwerwe]]",
+        status
+    );
+
+    config.paths = "./target, ./target/sdfsdfwersdfsdf".to_string();
+    let (healthy, status) = wait::check(&config).unwrap();
+    assert!(!healthy);
+    assert!(status.starts_with(
+        "unhealthy: 2/4 targets available, not available: [path [./target/sdfsdfwersdfsdf]]"
+    ));
+
+    assert!(millis_elapsed(start) < 500);
+}
+
+#[test]
+fn should_run_the_timeout_hook_before_exiting() {
+    let path = format!("./target/{}.hook", rand::random::<u64>());
+    let mut sleeper = MillisSleeper::default();
+
+    let hook_ran_before_timeout = std::cell::Cell::new(false);
+    let mut fun = || {
+        hook_ran_before_timeout.set(std::path::Path::new(&path).exists());
+    };
+
+    let mut config = new_config("", "./target/fsdfsdfwerwerwerwerwe", 100, 0, 0, 10, 1);
+    config.hooks.on_timeout = wait::parse_command(format!(
+        "sh -c 'echo $WAIT_EVENT $WAIT_EVENT_KIND $WAIT_EVENT_TARGET $WAIT_EVENT_REASON > {}'",
+        path
+    ))
+    .unwrap();
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+
+    assert!(hook_ran_before_timeout.get());
+    assert_eq!(
+        "timeout path ./target/fsdfsdfwerwerwerwerwe path missing\n",
+        std::fs::read_to_string(&path).unwrap()
+    );
+}
+
+#[test]
+fn should_write_the_marker_file_once_ready() {
+    let path = format!("./target/{}.ready", rand::random::<u64>());
+    std::fs::write(&path, "stale").unwrap();
+    let mut sleeper = MillisSleeper::default();
+
+    let count: atomic_counter::RelaxedCounter = atomic_counter::RelaxedCounter::new(0);
+    let mut fun = || {
+        count.inc();
+    };
+
+    let mut config = new_config("", "./target/fsdfsdfwerwerwerwerwe", 100, 0, 0, 10, 1);
+    config.marker = Some(wait::marker::Marker {
+        path: path.clone(),
+        content: "{targets}".to_string(),
+    });
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+    assert_eq!(1, count.get());
+    assert!(!std::path::Path::new(&path).exists());
+
+    config.paths = "./target".to_string();
+    wait::wait(&mut sleeper, &config, &mut fun).unwrap();
+    assert_eq!(1, count.get());
+    assert_eq!("path [./target]", std::fs::read_to_string(&path).unwrap());
+}
+
 fn on_timeout() {}
 
 fn new_config(
@@ -474,6 +1197,7 @@ fn new_config(
         wait_before: before,
         wait_after: after,
         wait_sleep_interval: sleep,
+        ..Default::default()
     }
 }
 